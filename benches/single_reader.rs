@@ -0,0 +1,83 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use flashmap::Builder;
+
+const KEYS: u64 = 1024;
+
+fn builders() -> [(&'static str, Builder); 2] {
+    [
+        ("general", Builder::new()),
+        ("single_reader", Builder::new().single_reader()),
+    ]
+}
+
+fn publish(c: &mut Criterion) {
+    let mut group = c.benchmark_group("publish");
+
+    for (name, builder) in builders() {
+        group.bench_function(BenchmarkId::new("idle_reader", name), |b| {
+            let (mut write, read) = builder.clone().build::<u64, u64>();
+            let mut key = 0;
+
+            b.iter(|| {
+                write.guard().insert(key % KEYS, key);
+                key += 1;
+            });
+
+            drop(read);
+        });
+
+        group.bench_function(BenchmarkId::new("busy_reader", name), |b| {
+            let (mut write, read) = builder.clone().build::<u64, u64>();
+            let stop = AtomicBool::new(false);
+
+            thread::scope(|s| {
+                s.spawn(|| {
+                    while !stop.load(Ordering::Relaxed) {
+                        black_box(read.guard().get(&0).copied());
+                    }
+                });
+
+                let mut key = 0;
+                b.iter(|| {
+                    write.guard().insert(key % KEYS, key);
+                    key += 1;
+                });
+
+                stop.store(true, Ordering::Relaxed);
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read");
+
+    for (name, builder) in builders() {
+        group.bench_function(BenchmarkId::new("guard_get", name), |b| {
+            let (mut write, read) = builder.clone().build::<u64, u64>();
+            let mut guard = write.guard();
+            for key in 0..KEYS {
+                guard.insert(key, key);
+            }
+            drop(guard);
+
+            let mut key = 0;
+            b.iter(|| {
+                black_box(read.guard().get(&(key % KEYS)).copied());
+                key += 1;
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, publish, read);
+criterion_main!(benches);
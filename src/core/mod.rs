@@ -17,21 +17,58 @@ use crate::{
         thread::{self, Thread},
     },
     util::{likely, lock, Alias},
+    write::{EntryVersions, InsertionOrder},
+};
+use crate::{
+    registry::Membership, util::CachePadded, BuilderArgs, Map, ReadHandle, Registry,
+    ResidualOverflow, WriteHandle, WriterParker,
 };
-use crate::{util::CachePadded, BuilderArgs, Map, ReadHandle, WriteHandle};
 use std::hash::{BuildHasher, Hash};
 use std::marker::PhantomData;
+use std::mem;
+use std::num::NonZeroUsize;
 use std::process::abort;
-use std::ptr::NonNull;
+use std::ptr::{self, NonNull};
+use std::sync::{PoisonError, TryLockError};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+const SINGLE_READER: &str = "Map was built with Builder::single_reader and already has a reader";
 
 pub struct Core<K, V, S = DefaultHashBuilder> {
     residual: AtomicIsize,
     // All readers need to be dropped before we're dropped, so we don't need to worry about
     // freeing any refcounts.
     refcounts: Mutex<Slab<NonNull<RefCount>>>,
+    // The refcount of the only read handle, if the map was built for a single reader. In that
+    // case `refcounts` is always empty, and this refcount is only freed when the core is dropped.
+    single_refcount: Option<NonNull<RefCount>>,
     writer_thread: UnsafeCell<Option<Thread>>,
+    // The waker of an asynchronous synchronization, which takes precedence over the writer thread
+    // and parker. This is only accessed on the slow path where the writer is waiting for readers,
+    // and async waits are not modeled by loom, so this is always a std mutex.
+    writer_waker: std::sync::Mutex<Option<Waker>>,
+    // Values evicted by the latest publish, if the map was built to reclaim them eagerly. These
+    // are dropped by whoever first sees that no reader is viewing the previously published map.
+    // Publishing holds this lock, so that the residual count is accurate whenever it's observed
+    // with the lock held. Like `writer_waker`, this is not modeled by loom.
+    evicted: Option<std::sync::Mutex<Vec<Alias<V>>>>,
     writer_map: Cell<MapIndex>,
     maps: OwnedMapAccess<K, V, S>,
+    // The insertion order of each map, if the map was built as ordered
+    orders: Option<[UnsafeCell<InsertionOrder<K>>; 2]>,
+    // The entry versions of each map, if the map was built as versioned
+    versions: Option<[UnsafeCell<EntryVersions<K>>; 2]>,
+    // The number of publishes which made changes visible, as of each map's contents
+    snapshot_versions: [UnsafeCell<u64>; 2],
+    residual_overflow: ResidualOverflow,
+    cache_padding: bool,
+    parker: Option<std::sync::Arc<dyn WriterParker>>,
+    _registry: Option<Membership>,
+    // Whether a write handle has been created for this core. The whole design rests on there
+    // being exactly one writer, so debug builds check that a second one is never created.
+    #[cfg(debug_assertions)]
+    writer_claimed: std::cell::Cell<bool>,
     _not_sync: PhantomData<*const u8>,
 }
 
@@ -51,53 +88,189 @@ where
     pub(crate) unsafe fn build_map(
         args: BuilderArgs<S>,
     ) -> (WriteHandle<K, V, S>, ReadHandle<K, V, S>) {
-        let BuilderArgs { capacity, h1, h2 } = args;
+        let BuilderArgs {
+            capacity,
+            h1,
+            h2,
+            residual_overflow,
+            cache_padding,
+            parker,
+            registry,
+            ordered,
+            versioned,
+            prefault,
+            single_reader,
+            expected_readers,
+            eager_reclaim,
+        } = args;
+
+        let mut first = Map::with_capacity_and_hasher(capacity, h1);
+        let mut second = Map::with_capacity_and_hasher(capacity, h2);
+        if prefault {
+            Self::prefault(&mut first);
+            Self::prefault(&mut second);
+        }
 
-        let maps = Box::new([
-            CachePadded::new(UnsafeCell::new(Map::with_capacity_and_hasher(capacity, h1))),
-            CachePadded::new(UnsafeCell::new(Map::with_capacity_and_hasher(capacity, h2))),
-        ]);
+        let maps = OwnedMapAccess::new(first, second, cache_padding);
 
-        let init_refcount_capacity = if cfg!(not(miri)) { num_cpus::get() } else { 1 };
+        let init_refcount_capacity = expected_readers.unwrap_or_else(|| {
+            if cfg!(not(miri)) {
+                std::thread::available_parallelism().map_or(1, NonZeroUsize::get)
+            } else {
+                1
+            }
+        });
 
         let me = Arc::new(Self {
             residual: AtomicIsize::new(0),
             refcounts: Mutex::new(Slab::with_capacity(init_refcount_capacity)),
+            single_refcount: single_reader
+                .then(|| Self::alloc_refcount(MapIndex::First, cache_padding)),
             writer_thread: UnsafeCell::new(None),
+            writer_waker: std::sync::Mutex::new(None),
+            evicted: eager_reclaim.then(|| std::sync::Mutex::new(Vec::new())),
             writer_map: Cell::new(MapIndex::Second),
-            maps: OwnedMapAccess::new(maps),
+            maps,
+            orders: ordered.then(|| [UnsafeCell::new(Vec::new()), UnsafeCell::new(Vec::new())]),
+            versions: versioned.then(|| {
+                [
+                    UnsafeCell::new(EntryVersions::default()),
+                    UnsafeCell::new(EntryVersions::default()),
+                ]
+            }),
+            snapshot_versions: [UnsafeCell::new(0), UnsafeCell::new(0)],
+            residual_overflow,
+            cache_padding,
+            parker,
+            _registry: registry.as_ref().map(Registry::join),
+            #[cfg(debug_assertions)]
+            writer_claimed: std::cell::Cell::new(false),
             _not_sync: PhantomData,
         });
 
         let write_handle = unsafe { WriteHandle::new(Arc::clone(&me)) };
-        let read_handle = Self::new_reader(me);
+        let read_handle = match me.single_refcount {
+            Some(refcount) => {
+                let map_access = me.maps.share();
+                ReadHandle::new(me, map_access, refcount, 0)
+            }
+            None => Self::new_reader(me),
+        };
 
         (write_handle, read_handle)
     }
+
+    /// Writes to every bucket of the given empty map, so that the pages backing its table are
+    /// faulted in. The control bytes are already initialized when the table is allocated.
+    fn prefault(map: &mut Map<K, V, S>) {
+        debug_assert!(map.is_empty());
+
+        // Empty tables point to a static singleton rather than an allocation
+        if map.capacity() == 0 {
+            return;
+        }
+
+        let table = map.raw_table();
+        let buckets = table.buckets();
+        // Safety: the table is allocated, and its data section consists of exactly `buckets`
+        // elements directly preceding `data_end`. Every bucket is empty, so it is fine to
+        // overwrite them.
+        unsafe {
+            let data_start = table.data_end().as_ptr().sub(buckets);
+            ptr::write_bytes(data_start, 0, buckets);
+        }
+    }
 }
 
 impl<K, V, S> Core<K, V, S> {
     pub fn new_reader(me: Arc<Self>) -> ReadHandle<K, V, S> {
+        assert!(me.single_refcount.is_none(), "{}", SINGLE_READER);
+
         let mut guard = lock(&me.refcounts);
-        let refcount = RefCount::new(me.writer_map.get().other());
-        let refcount = NonNull::new(Box::into_raw(Box::new(refcount))).unwrap();
-        let key = guard.insert(refcount);
+        let (refcount, key) = me.register_refcount(&mut guard);
         drop(guard);
 
         let map_access = me.maps.share();
         ReadHandle::new(me, map_access, refcount, key)
     }
 
+    pub fn new_readers(me: Arc<Self>, count: usize) -> Vec<ReadHandle<K, V, S>> {
+        assert!(
+            count == 0 || me.single_refcount.is_none(),
+            "{}",
+            SINGLE_READER
+        );
+
+        let mut guard = lock(&me.refcounts);
+        guard.reserve(count);
+        let refcounts = (0..count)
+            .map(|_| me.register_refcount(&mut guard))
+            .collect::<Vec<_>>();
+        drop(guard);
+
+        refcounts
+            .into_iter()
+            .map(|(refcount, key)| ReadHandle::new(Arc::clone(&me), me.maps.share(), refcount, key))
+            .collect()
+    }
+
+    /// Allocates a new refcount and registers it in the given slab, which must be the locked
+    /// contents of `self.refcounts`.
+    fn register_refcount(
+        &self,
+        refcounts: &mut Slab<NonNull<RefCount>>,
+    ) -> (NonNull<RefCount>, usize) {
+        let refcount = Self::alloc_refcount(self.writer_map.get().other(), self.cache_padding);
+        let key = refcounts.insert(refcount);
+        (refcount, key)
+    }
+
+    fn alloc_refcount(read_index: MapIndex, cache_padding: bool) -> NonNull<RefCount> {
+        let refcount = RefCount::new(read_index);
+        let refcount = if cache_padding {
+            // CachePadded is repr(C), so the refcount is at offset zero
+            Box::into_raw(Box::new(CachePadded::new(refcount))).cast::<RefCount>()
+        } else {
+            Box::into_raw(Box::new(refcount))
+        };
+        NonNull::new(refcount).unwrap()
+    }
+
+    /// Frees a refcount allocated by `alloc_refcount`.
+    unsafe fn free_refcount(&self, refcount: NonNull<RefCount>) {
+        let refcount = refcount.as_ptr();
+
+        if self.cache_padding {
+            drop(unsafe { Box::from_raw(refcount.cast::<CachePadded<RefCount>>()) });
+        } else {
+            drop(unsafe { Box::from_raw(refcount) });
+        }
+    }
+
     pub unsafe fn release_refcount(&self, key: usize) {
-        let refcount = lock(&self.refcounts).remove(key);
+        // The refcount of a single reader is still read when publishing, so it's freed when the
+        // core is dropped instead
+        if self.single_refcount.is_some() {
+            return;
+        }
 
-        drop(unsafe { Box::from_raw(refcount.as_ptr()) });
+        let refcount = lock(&self.refcounts).remove(key);
+        unsafe { self.free_refcount(refcount) };
     }
 
     #[inline]
     pub unsafe fn release_residual(&self) {
         let last_residual = self.residual.fetch_sub(1, Ordering::AcqRel);
 
+        // If we were the last residual reader, but the writer is not waiting for us, then all
+        // that's left to do is to drop any evicted values.
+        if last_residual == 1 {
+            if self.evicted.is_some() {
+                self.try_drop_evicted();
+            }
+            return;
+        }
+
         // If we were not the last residual reader, or the writer is not currently waiting for the
         // last reader, we do nothing.
         if last_residual != isize::MIN + 1 {
@@ -108,6 +281,25 @@ impl<K, V, S> Core<K, V, S> {
 
         // Since we were the last reader, and the writer was waiting on us, it's our job to wake it
         // up.
+        self.unpark();
+
+        if self.evicted.is_some() {
+            self.try_drop_evicted();
+        }
+    }
+
+    #[inline]
+    fn unpark(&self) {
+        if let Some(waker) = self.take_writer_waker() {
+            waker.wake();
+            return;
+        }
+
+        if let Some(parker) = &self.parker {
+            parker.unpark();
+            return;
+        }
+
         self.writer_thread.with(|ptr| match unsafe { &*ptr } {
             Some(thread) => thread.unpark(),
             // This branch is entirely unreachable (assuming this library is coded correctly),
@@ -127,20 +319,58 @@ impl<K, V, S> Core<K, V, S> {
         });
     }
 
+    /// Records which thread needs to be woken up once the last residual reader is released. This
+    /// must be called before setting the waiting flag on the residual count.
+    #[inline]
+    fn prepare_park(&self) {
+        if self.parker.is_none() {
+            let current = Some(thread::current());
+            self.writer_thread.with_mut(|ptr| unsafe { *ptr = current });
+        }
+
+        // A waker left over from an earlier asynchronous wait would otherwise be woken instead.
+        // This happens after recording the thread, so that a reader which finds no waker also
+        // sees the thread.
+        drop(self.take_writer_waker());
+    }
+
+    #[inline]
+    fn take_writer_waker(&self) -> Option<Waker> {
+        self.writer_waker
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    #[inline]
+    fn park(&self) {
+        match &self.parker {
+            Some(parker) => parker.park(),
+            None => thread::park(),
+        }
+    }
+
+    #[inline]
+    fn park_timeout(&self, timeout: Duration) {
+        match &self.parker {
+            Some(parker) => parker.park_timeout(timeout),
+            None => thread::park_timeout(timeout),
+        }
+    }
+
     #[inline]
     pub fn synchronize(&self) {
         let residual = self.residual.load(Ordering::Acquire);
 
         if residual != 0 {
-            let current = Some(thread::current());
-            self.writer_thread.with_mut(|ptr| unsafe { *ptr = current });
+            self.prepare_park();
 
             let latest_residual = self.residual.fetch_add(isize::MIN, Ordering::AcqRel);
 
             if likely(latest_residual != 0) {
                 loop {
                     // Wait for the next writable map to become available
-                    thread::park();
+                    self.park();
 
                     let residual = self.residual.load(Ordering::Acquire);
                     if likely(residual == 0) {
@@ -155,17 +385,372 @@ impl<K, V, S> Core<K, V, S> {
         }
     }
 
+    /// Like `synchronize`, but gives up after the given timeout. Returns whether synchronization
+    /// completed.
+    pub fn synchronize_timeout(&self, timeout: Duration) -> bool {
+        let deadline = match Instant::now().checked_add(timeout) {
+            Some(deadline) => deadline,
+            None => {
+                self.synchronize();
+                return true;
+            }
+        };
+
+        if self.residual.load(Ordering::Acquire) == 0 {
+            return true;
+        }
+
+        self.prepare_park();
+
+        let latest_residual = self.residual.fetch_add(isize::MIN, Ordering::AcqRel);
+
+        if latest_residual == 0 {
+            self.residual.store(0, Ordering::Release);
+            return true;
+        }
+
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+
+            self.park_timeout(deadline - now);
+
+            if self.residual.load(Ordering::Acquire) == 0 {
+                return true;
+            }
+        }
+
+        // We timed out, so we need to remove the waiting flag in order for readers to stop trying
+        // to wake us up.
+        self.stop_waiting()
+    }
+
+    /// Polls for every reader to move off of the writer's map, registering the given context to be
+    /// woken once they have. `waiting` tracks whether the writer is currently waiting on readers,
+    /// and must start out as false. If this returns pending, then the wait must either be polled
+    /// to completion, or be abandoned through `stop_waiting`.
+    pub fn poll_synchronize(&self, waiting: &mut bool, cx: &mut Context<'_>) -> Poll<()> {
+        if *waiting {
+            {
+                let mut waker = self
+                    .writer_waker
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                if !waker
+                    .as_ref()
+                    .is_some_and(|waker| waker.will_wake(cx.waker()))
+                {
+                    *waker = Some(cx.waker().clone());
+                }
+            }
+
+            // The last reader may have taken the previous waker before we replaced it, so check
+            // after registering the new one.
+            if self.residual.load(Ordering::Acquire) == 0 {
+                *waiting = false;
+                return Poll::Ready(());
+            }
+
+            return Poll::Pending;
+        }
+
+        if self.residual.load(Ordering::Acquire) == 0 {
+            return Poll::Ready(());
+        }
+
+        *self
+            .writer_waker
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(cx.waker().clone());
+
+        let latest_residual = self.residual.fetch_add(isize::MIN, Ordering::AcqRel);
+
+        if latest_residual == 0 {
+            self.residual.store(0, Ordering::Release);
+            return Poll::Ready(());
+        }
+
+        *waiting = true;
+        Poll::Pending
+    }
+
+    /// Removes the waiting flag from the residual count, so that readers stop trying to wake the
+    /// writer. Returns true if the last reader released its guard before the flag could be
+    /// removed, in which case the wait completed anyway.
+    pub fn stop_waiting(&self) -> bool {
+        let mut residual = self.residual.load(Ordering::Acquire);
+        loop {
+            if residual == 0 {
+                return true;
+            }
+
+            // The last residual reader has released its guard, and is about to store 0 and wake
+            // us up, so the wait is effectively over.
+            if residual == isize::MIN {
+                while self.residual.load(Ordering::Acquire) != 0 {
+                    thread::yield_now();
+                }
+                return true;
+            }
+
+            debug_assert!(residual < 0);
+
+            match self.residual.compare_exchange_weak(
+                residual,
+                residual.wrapping_sub(isize::MIN),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return false,
+                Err(latest) => residual = latest,
+            }
+        }
+    }
+
+    /// Returns whether every reader has moved off of the writer's map, without blocking. If this
+    /// returns true, then `synchronize` would be a no-op.
+    #[inline]
+    pub fn is_synchronized(&self) -> bool {
+        self.residual.load(Ordering::Acquire) == 0
+    }
+
+    /// Returns the total number of live read guards across all read handles. This is only a
+    /// snapshot, since readers may create and drop guards concurrently.
+    pub fn open_guards(&self) -> usize {
+        if let Some(refcount) = self.single_refcount {
+            return unsafe { refcount.as_ref() }.count();
+        }
+
+        lock(&self.refcounts)
+            .iter()
+            .map(|(_, refcount)| unsafe { refcount.as_ref() }.count())
+            .fold(0, usize::saturating_add)
+    }
+
+    #[cfg(feature = "diagnostics")]
+    pub fn lagging_readers(&self) -> Vec<std::thread::ThreadId> {
+        if self.residual.load(Ordering::Acquire) == 0 {
+            return Vec::new();
+        }
+
+        if let Some(refcount) = self.single_refcount {
+            let refcount = unsafe { refcount.as_ref() };
+            return (refcount.count() != 0)
+                .then(|| refcount.owner())
+                .into_iter()
+                .collect();
+        }
+
+        let guard = lock(&self.refcounts);
+        guard
+            .iter()
+            .map(|(_, refcount)| unsafe { refcount.as_ref() })
+            .filter(|refcount| refcount.count() != 0)
+            .map(RefCount::owner)
+            .collect()
+    }
+
+    /// Estimates the number of bytes used by both maps, not including any heap memory owned by
+    /// the keys and values.
+    pub fn memory_usage(&self) -> usize {
+        [MapIndex::First, MapIndex::Second]
+            .into_iter()
+            .map(|index| {
+                let capacity = self.maps.get(index).with(|ptr| unsafe { &*ptr }.capacity());
+                mem::size_of::<Map<K, V, S>>() + Self::table_size(capacity)
+            })
+            .sum()
+    }
+
+    /// Computes the size of the allocation backing a `hashbrown` table with the given capacity.
+    fn table_size(capacity: usize) -> usize {
+        // hashbrown never allocates for empty tables
+        if capacity == 0 {
+            return 0;
+        }
+
+        // This inverts hashbrown's conversion from a bucket count to a capacity. Tables with
+        // fewer than 8 buckets can use all but one of them, and larger tables have a maximum load
+        // factor of 7/8.
+        let buckets = if capacity < 8 {
+            capacity + 1
+        } else {
+            capacity / 7 * 8
+        };
+
+        // Each bucket holds one entry and one control byte, and the control bytes are followed
+        // by an extra group's worth of bytes to allow for unaligned group loads.
+        const GROUP_WIDTH: usize = 16;
+        buckets * (mem::size_of::<(Alias<K>, Alias<V>)>() + 1) + GROUP_WIDTH
+    }
+
+    /// Marks this core as having a write handle, panicking if it already has one.
+    #[cfg(debug_assertions)]
+    pub fn debug_claim_writer(&self) {
+        assert!(
+            !self.writer_claimed.replace(true),
+            "Created a second write handle for the same map"
+        );
+    }
+
     #[inline]
     pub fn writer_map(&self) -> &UnsafeCell<Map<K, V, S>> {
         self.maps.get(self.writer_map.get())
     }
 
+    /// Returns the map which was most recently published. Since a write guard always publishes
+    /// when dropped, this map reflects every write made so far whenever no write guard is live.
+    /// It is only ever read by both readers and the writer until the next call to `synchronize`.
+    #[inline]
+    pub fn published_map(&self) -> &UnsafeCell<Map<K, V, S>> {
+        self.maps.get(self.writer_map.get().other())
+    }
+
+    /// Returns whether evicted values are handed over to readers to drop.
+    #[inline]
+    pub fn reclaims_eagerly(&self) -> bool {
+        self.evicted.is_some()
+    }
+
+    /// Returns whether the map keeps track of insertion order.
+    #[inline]
+    pub fn is_ordered(&self) -> bool {
+        self.orders.is_some()
+    }
+
+    /// Returns the insertion order of the map at the given index, if the map is ordered.
+    #[inline]
+    pub fn insertion_order(&self, map_index: MapIndex) -> Option<&UnsafeCell<InsertionOrder<K>>> {
+        self.orders
+            .as_ref()
+            .map(|orders| &orders[map_index as usize])
+    }
+
+    /// Returns the insertion order of the writer's map, if the map is ordered.
+    #[inline]
+    pub fn writer_insertion_order(&self) -> Option<&UnsafeCell<InsertionOrder<K>>> {
+        self.insertion_order(self.writer_map.get())
+    }
+
+    /// Returns the snapshot version of the map at the given index. The caller must either hold a
+    /// guard for that map, or be the writer.
+    #[inline]
+    pub fn snapshot_version(&self, map_index: MapIndex) -> u64 {
+        self.snapshot_versions[map_index as usize].with(|version_ptr| unsafe { *version_ptr })
+    }
+
+    /// Advances the snapshot version of the writer's map past that of the published map. The
+    /// caller must be the writer, and no readers may be viewing the writer's map.
+    #[inline]
+    pub unsafe fn advance_snapshot_version(&self) {
+        let writer_map = self.writer_map.get();
+        let version = self.snapshot_version(writer_map.other()) + 1;
+        self.snapshot_versions[writer_map as usize]
+            .with_mut(|version_ptr| unsafe { *version_ptr = version });
+    }
+
+    /// Returns whether the map keeps track of entry versions.
+    #[inline]
+    pub fn is_versioned(&self) -> bool {
+        self.versions.is_some()
+    }
+
+    /// Returns the entry versions of the map at the given index, if the map is versioned.
+    #[inline]
+    pub fn entry_versions(&self, map_index: MapIndex) -> Option<&UnsafeCell<EntryVersions<K>>> {
+        self.versions
+            .as_ref()
+            .map(|versions| &versions[map_index as usize])
+    }
+
+    /// Returns the entry versions of the writer's map, if the map is versioned.
+    #[inline]
+    pub fn writer_entry_versions(&self) -> Option<&UnsafeCell<EntryVersions<K>>> {
+        self.entry_versions(self.writer_map.get())
+    }
+
+    /// Publishes the writer's map, handing over the values evicted by the published changes to
+    /// be dropped once no reader can observe them. The evicted values must be empty unless the
+    /// map reclaims them eagerly.
+    #[inline]
+    pub unsafe fn publish(&self, evicted: Vec<Alias<V>>) {
+        let Some(pending) = &self.evicted else {
+            debug_assert!(evicted.is_empty());
+            return unsafe { self.publish_maps() };
+        };
+
+        let mut pending = pending.lock().unwrap_or_else(PoisonError::into_inner);
+        pending.extend(evicted);
+        unsafe { self.publish_maps() };
+
+        // If no reader was viewing the previous map, then no reader will drop the values
+        if self.residual.load(Ordering::Acquire) == 0 {
+            let evicted = mem::take(&mut *pending);
+            drop(pending);
+            Self::drop_evicted(evicted);
+        }
+    }
+
+    /// Drops the evicted values handed over by the last publish, if there are any and no reader
+    /// can still observe them. This never blocks, and leaves the values to the writer if it's in
+    /// the middle of publishing.
+    #[cold]
+    fn try_drop_evicted(&self) {
+        let Some(pending) = &self.evicted else {
+            return;
+        };
+
+        let mut pending = match pending.try_lock() {
+            Ok(pending) => pending,
+            Err(TryLockError::Poisoned(error)) => error.into_inner(),
+            Err(TryLockError::WouldBlock) => return,
+        };
+
+        // Another publish may have happened since the residual count reached zero, in which case
+        // the values belong to readers viewing the now previous map
+        if self.residual.load(Ordering::Acquire) != 0 {
+            return;
+        }
+
+        let evicted = mem::take(&mut *pending);
+        drop(pending);
+        Self::drop_evicted(evicted);
+    }
+
+    /// Drops any evicted values which readers have not dropped yet. The caller must be the writer,
+    /// and no readers may be viewing the writer's map.
     #[inline]
-    pub unsafe fn publish(&self) {
+    pub unsafe fn reclaim_evicted(&self) {
+        if let Some(pending) = &self.evicted {
+            let evicted = mem::take(&mut *pending.lock().unwrap_or_else(PoisonError::into_inner));
+            Self::drop_evicted(evicted);
+        }
+    }
+
+    fn drop_evicted(evicted: Vec<Alias<V>>) {
+        for mut value in evicted {
+            unsafe { Alias::drop(&mut value) };
+        }
+    }
+
+    #[inline]
+    unsafe fn publish_maps(&self) {
         debug_assert_eq!(self.residual.load(Ordering::Relaxed), 0);
 
         fence(Ordering::Release);
 
+        if let Some(refcount) = self.single_refcount {
+            // No readers can be registered concurrently, so there's nothing to lock. A single
+            // refcount never exceeds isize::MAX, so the residual cannot overflow either.
+            self.writer_map.set(self.writer_map.get().other());
+            let count = unsafe { refcount.as_ref() }.swap_maps() as isize;
+            self.residual.fetch_add(count, Ordering::Relaxed);
+            fence(Ordering::Acquire);
+            return;
+        }
+
         let guard = lock(&self.refcounts);
 
         // This needs to be within the mutex
@@ -179,12 +764,12 @@ impl<K, V, S> Core<K, V, S> {
             let refcount = unsafe { refcount.as_ref() };
 
             // Because the highest bit is used in the refcount, this cast will not be lossy
-            initial_residual += refcount.swap_maps() as isize;
+            let count = refcount.swap_maps() as isize;
 
-            // If we overflowed, then abort.
-            if initial_residual < 0 {
-                abort();
-            }
+            initial_residual = match initial_residual.checked_add(count) {
+                Some(residual) => residual,
+                None => self.residual_overflow(),
+            };
         }
 
         drop(guard);
@@ -193,10 +778,29 @@ impl<K, V, S> Core<K, V, S> {
 
         fence(Ordering::Acquire);
     }
+
+    /// Handles the case where the residual accumulated in `publish` overflows.
+    #[cold]
+    #[inline(never)]
+    fn residual_overflow(&self) -> ! {
+        if let ResidualOverflow::AbortWith(hook) = self.residual_overflow {
+            hook();
+        }
+        abort()
+    }
 }
 
 impl<K, V, S> Drop for Core<K, V, S> {
     fn drop(&mut self) {
+        if let Some(refcount) = self.single_refcount {
+            unsafe { self.free_refcount(refcount) };
+        }
+
+        if let Some(pending) = &mut self.evicted {
+            let evicted = mem::take(pending.get_mut().unwrap_or_else(PoisonError::into_inner));
+            Self::drop_evicted(evicted);
+        }
+
         let reader_map_index = self.writer_map.get().other();
         self.maps.get(reader_map_index).with_mut(|ptr| unsafe {
             (*ptr).drain().for_each(|(ref mut key, ref mut value)| {
@@ -206,3 +810,58 @@ impl<K, V, S> Drop for Core<K, V, S> {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, ResidualOverflow};
+    use std::{
+        env,
+        io::{self, Write},
+        process::Command,
+    };
+
+    const CHILD_ENV: &str = "FLASHMAP_RESIDUAL_OVERFLOW_CHILD";
+
+    /// Makes the refcount visited last while publishing report enough guards to overflow the
+    /// residual, on top of the real guards counted by the refcounts visited before it.
+    fn overflow_last_refcount<K, V, S>(core: &Core<K, V, S>) -> NonNull<RefCount> {
+        let refcounts = lock(&core.refcounts);
+        let (_, &refcount) = refcounts.iter().next_back().unwrap();
+        unsafe { refcount.as_ref() }.add_phantom_guards(isize::MAX as usize);
+        refcount
+    }
+
+    #[test]
+    fn residual_overflow_abort_with_runs_hook() {
+        if env::var_os(CHILD_ENV).is_some() {
+            fn hook() {
+                let _ = writeln!(io::stderr(), "residual overflow hook ran");
+            }
+
+            let (mut write, read) = Builder::new()
+                .on_residual_overflow(ResidualOverflow::AbortWith(hook))
+                .build::<u32, u32>();
+            let _overflowing_read = read.clone();
+
+            let _guard = read.guard();
+            overflow_last_refcount(write.core());
+            write.guard().insert(1, 1);
+            unreachable!("Publishing did not abort");
+        }
+
+        // Aborting takes down the whole process, so the overflow happens in a child process
+        let output = Command::new(env::current_exe().unwrap())
+            .args([
+                "core::tests::residual_overflow_abort_with_runs_hook",
+                "--exact",
+                "--nocapture",
+            ])
+            .env(CHILD_ENV, "1")
+            .output()
+            .unwrap();
+
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("residual overflow hook ran"));
+    }
+}
@@ -1,12 +1,15 @@
 use crate::loom::sync::atomic::{AtomicUsize, Ordering};
-use crate::util::CachePadded;
 use std::process::abort;
 use std::ptr;
+#[cfg(feature = "diagnostics")]
+use std::thread::{self, ThreadId};
 
 use super::MapIndex;
 
 pub struct RefCount {
-    value: CachePadded<AtomicUsize>,
+    value: AtomicUsize,
+    #[cfg(feature = "diagnostics")]
+    owner: ThreadId,
 }
 
 impl RefCount {
@@ -19,10 +22,26 @@ impl RefCount {
 
     pub(super) fn new(read_index: MapIndex) -> Self {
         Self {
-            value: CachePadded::new(AtomicUsize::new((read_index as usize) << (usize::BITS - 1))),
+            value: AtomicUsize::new((read_index as usize) << (usize::BITS - 1)),
+            #[cfg(feature = "diagnostics")]
+            owner: thread::current().id(),
         }
     }
 
+    /// The thread which created the read handle associated with this refcount.
+    #[cfg(feature = "diagnostics")]
+    #[inline]
+    pub(super) fn owner(&self) -> ThreadId {
+        self.owner
+    }
+
+    /// Returns the number of guards currently alive for this refcount. This value is only a
+    /// snapshot and may be stale by the time it is used.
+    #[inline]
+    pub(super) fn count(&self) -> usize {
+        self.value.load(Ordering::Relaxed) & Self::COUNT_MASK
+    }
+
     #[inline]
     fn to_map_index(value: usize) -> MapIndex {
         unsafe { MapIndex::from_usize_unchecked(value >> (usize::BITS - 1)) }
@@ -56,12 +75,26 @@ impl RefCount {
         Self::to_map_index(old_value)
     }
 
+    /// Returns the map which new guards created through this refcount will view. This value is
+    /// only a snapshot and may be stale by the time it is used.
+    #[inline]
+    pub fn map_index(&self) -> MapIndex {
+        Self::to_map_index(self.value.load(Ordering::Relaxed))
+    }
+
     #[inline]
     pub fn decrement(&self) -> MapIndex {
         let old_value = self.value.fetch_sub(1, Ordering::Release);
         Self::to_map_index(old_value)
     }
 
+    /// Adds to the count without creating any guards, which simulates guards being leaked on a
+    /// scale that is impractical to reach in tests.
+    #[cfg(test)]
+    pub(super) fn add_phantom_guards(&self, count: usize) {
+        self.value.fetch_add(count, Ordering::Relaxed);
+    }
+
     /// The returned value, which we'll call `c`, will always satisfy `0 <= c <= isize::MAX`.
     #[inline]
     pub(super) fn swap_maps(&self) -> usize {
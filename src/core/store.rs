@@ -1,7 +1,11 @@
 use crate::loom::cell::UnsafeCell;
 use crate::util::CachePadded;
 use crate::Map;
-use std::{marker::PhantomData, mem, ptr::NonNull};
+use std::{
+    marker::PhantomData,
+    mem,
+    ptr::{self, NonNull},
+};
 
 #[repr(usize)]
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -26,17 +30,46 @@ impl MapIndex {
     }
 }
 
-type MapArray<K, V, S> = [CachePadded<UnsafeCell<Map<K, V, S>>>; 2];
+type PaddedMapArray<K, V, S> = [CachePadded<UnsafeCell<Map<K, V, S>>>; 2];
+type MapArray<K, V, S> = [UnsafeCell<Map<K, V, S>>; 2];
 
 pub struct OwnedMapAccess<K, V, S> {
     access: SharedMapAccess<K, V, S>,
+    padded: bool,
     _dropck: PhantomData<MapArray<K, V, S>>,
 }
 
 impl<K, V, S> OwnedMapAccess<K, V, S> {
-    pub fn new(boxed: Box<MapArray<K, V, S>>) -> Self {
+    /// Allocates storage for the two given maps, optionally padding each to its own cache line.
+    pub fn new(first: Map<K, V, S>, second: Map<K, V, S>, padded: bool) -> Self {
+        let maps = if padded {
+            let boxed: Box<PaddedMapArray<K, V, S>> = Box::new([
+                CachePadded::new(UnsafeCell::new(first)),
+                CachePadded::new(UnsafeCell::new(second)),
+            ]);
+            let array = Box::into_raw(boxed);
+            // CachePadded is repr(C), so the padded values are at offset zero
+            unsafe {
+                [
+                    ptr::addr_of_mut!((*array)[0]).cast::<UnsafeCell<Map<K, V, S>>>(),
+                    ptr::addr_of_mut!((*array)[1]).cast::<UnsafeCell<Map<K, V, S>>>(),
+                ]
+            }
+        } else {
+            let boxed: Box<MapArray<K, V, S>> =
+                Box::new([UnsafeCell::new(first), UnsafeCell::new(second)]);
+            let array = Box::into_raw(boxed);
+            unsafe {
+                [
+                    ptr::addr_of_mut!((*array)[0]),
+                    ptr::addr_of_mut!((*array)[1]),
+                ]
+            }
+        };
+
         Self {
-            access: SharedMapAccess::new(NonNull::new(Box::into_raw(boxed)).unwrap()),
+            access: SharedMapAccess::new(maps.map(|map| NonNull::new(map).unwrap())),
+            padded,
             _dropck: PhantomData,
         }
     }
@@ -53,23 +86,29 @@ impl<K, V, S> OwnedMapAccess<K, V, S> {
 
 impl<K, V, S> Drop for OwnedMapAccess<K, V, S> {
     fn drop(&mut self) {
-        unsafe { drop(Box::from_raw(self.access.maps.as_ptr())) };
+        // The first map is at the start of the allocation in both layouts
+        let array = self.access.maps[MapIndex::First as usize].as_ptr();
+
+        if self.padded {
+            drop(unsafe { Box::from_raw(array.cast::<PaddedMapArray<K, V, S>>()) });
+        } else {
+            drop(unsafe { Box::from_raw(array.cast::<MapArray<K, V, S>>()) });
+        }
     }
 }
 
 pub struct SharedMapAccess<K, V, S> {
-    maps: NonNull<MapArray<K, V, S>>,
+    maps: [NonNull<UnsafeCell<Map<K, V, S>>>; 2],
 }
 
 impl<K, V, S> SharedMapAccess<K, V, S> {
-    fn new(maps: NonNull<MapArray<K, V, S>>) -> Self {
+    fn new(maps: [NonNull<UnsafeCell<Map<K, V, S>>>; 2]) -> Self {
         Self { maps }
     }
 
     #[inline]
     pub unsafe fn get(&self, map_index: MapIndex) -> &UnsafeCell<Map<K, V, S>> {
-        let maps = unsafe { self.maps.as_ref() };
-        &maps[map_index as usize]
+        unsafe { self.maps[map_index as usize].as_ref() }
     }
 }
 
@@ -6,14 +6,20 @@
 pub mod algorithm;
 mod core;
 mod read;
+mod registry;
+#[cfg(feature = "serde_json")]
+mod stream;
 mod util;
 mod view;
 mod write;
 
 pub use read::*;
+pub use registry::Registry;
+#[cfg(feature = "serde_json")]
+pub use stream::deserialize_from;
 pub(crate) use util::loom;
-pub use util::{deterministic::*, Alias};
-pub use view::View;
+pub use util::{deterministic::*, Alias, SeededHasher, SeededState};
+pub use view::{Diff, DiffEntry, Found, MapLike, View};
 pub use write::*;
 
 use self::core::Core;
@@ -21,6 +27,8 @@ use std::{
     collections::hash_map::RandomState,
     fmt::{self, Debug, Formatter},
     hash::{BuildHasher, Hash},
+    sync::Arc,
+    time::Duration,
 };
 
 pub(crate) type Map<K, V, S = RandomState> = hashbrown::HashMap<Alias<K>, Alias<V>, S>;
@@ -67,10 +75,32 @@ where
 ///
 /// This builder allows you to specify an initial capacity and a hasher, and provides more
 /// flexibility in how that hasher can be constructed.
-#[derive(Clone, Copy)]
+///
+/// Since version 0.2, `Builder` implements `Clone` but not `Copy`, since it can hold shared state
+/// such as a hasher generator closure or a [`WriterParker`](crate::WriterParker). Code which
+/// copied a builder to build several maps should clone it instead.
+///
+/// ```
+/// use flashmap::Builder;
+///
+/// let builder = Builder::new().with_capacity(16);
+/// let (write1, read1) = builder.clone().build::<u32, u32>();
+/// let (write2, read2) = builder.build::<u32, u32>();
+/// ```
+#[derive(Clone)]
 pub struct Builder<S = RandomState> {
     capacity: usize,
     hasher: HasherGen<S>,
+    residual_overflow: ResidualOverflow,
+    cache_padding: bool,
+    parker: Option<Arc<dyn WriterParker>>,
+    registry: Option<Registry>,
+    ordered: bool,
+    versioned: bool,
+    prefault: bool,
+    single_reader: bool,
+    expected_readers: Option<usize>,
+    eager_reclaim: bool,
 }
 
 impl<S> Debug for Builder<S> {
@@ -78,6 +108,16 @@ impl<S> Debug for Builder<S> {
         f.debug_struct("Builder")
             .field("capacity", &self.capacity)
             .field("hasher", &std::any::type_name::<S>())
+            .field("residual_overflow", &self.residual_overflow)
+            .field("cache_padding", &self.cache_padding)
+            .field("custom_parker", &self.parker.is_some())
+            .field("in_registry", &self.registry.is_some())
+            .field("ordered", &self.ordered)
+            .field("versioned", &self.versioned)
+            .field("prefault", &self.prefault)
+            .field("single_reader", &self.single_reader)
+            .field("expected_readers", &self.expected_readers)
+            .field("eager_reclaim", &self.eager_reclaim)
             .finish()
     }
 }
@@ -92,6 +132,16 @@ impl Builder<RandomState> {
                 let hasher = RandomState::default();
                 (hasher.clone(), hasher)
             }),
+            residual_overflow: ResidualOverflow::Abort,
+            cache_padding: true,
+            parker: None,
+            registry: None,
+            ordered: false,
+            versioned: false,
+            prefault: false,
+            single_reader: false,
+            expected_readers: None,
+            eager_reclaim: false,
         }
     }
 }
@@ -104,13 +154,360 @@ impl Default for Builder<RandomState> {
 
 impl<S> Builder<S> {
     /// Sets the initial capacity of the map. If not specified, the default is 0.
+    ///
+    /// This only affects the maps themselves. The list of registered read handles is sized
+    /// separately, see [`with_expected_readers`](crate::Builder::with_expected_readers).
     pub fn with_capacity(self, capacity: usize) -> Self {
+        Self { capacity, ..self }
+    }
+
+    /// Allocates both copies of the map with room for `capacity` entries up front, and writes to
+    /// the allocations so that the operating system backs them with physical memory immediately.
+    ///
+    /// [`with_capacity`](crate::Builder::with_capacity) already sizes both copies of the map, but
+    /// the memory may only be faulted in lazily, the first time each page is written to. For
+    /// latency-sensitive applications, this moves that cost from the first writes to the creation
+    /// of the map. Once built, both copies of the map can hold at least `capacity` entries without
+    /// reallocating, as reported by [`View::capacity`](crate::View::capacity).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::Builder;
+    ///
+    /// let (mut write, read) = Builder::new().prealloc(1000).build::<u64, u64>();
+    ///
+    /// // The reader and the writer each see a different copy of the map
+    /// assert!(read.guard().capacity() >= 1000);
+    /// assert!(write.guard().capacity() >= 1000);
+    /// ```
+    pub fn prealloc(self, capacity: usize) -> Self {
         Self {
             capacity,
-            hasher: self.hasher,
+            prefault: true,
+            ..self
+        }
+    }
+
+    /// Sets the behavior of the map when the number of outstanding read guards observed during a
+    /// publish exceeds `isize::MAX`. If not specified, the default is
+    /// [`ResidualOverflow::Abort`](crate::ResidualOverflow::Abort).
+    ///
+    /// See [`ResidualOverflow`](crate::ResidualOverflow) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::{Builder, ResidualOverflow};
+    ///
+    /// fn log_overflow() {
+    ///     eprintln!("flashmap: residual overflow, aborting");
+    /// }
+    ///
+    /// let (mut write, read) = Builder::new()
+    ///     .on_residual_overflow(ResidualOverflow::AbortWith(log_overflow))
+    ///     .build::<u32, u32>();
+    ///
+    /// write.guard().insert(1, 2);
+    /// assert_eq!(*read.guard().get(&1).unwrap(), 2);
+    /// ```
+    pub fn on_residual_overflow(self, residual_overflow: ResidualOverflow) -> Self {
+        Self {
+            residual_overflow,
+            ..self
+        }
+    }
+
+    /// Disables cache padding for the internal state of the map.
+    ///
+    /// By default, both copies of the map and the reference count of every read handle are
+    /// padded and aligned to the size of a cache line. This prevents unrelated writes from
+    /// invalidating the cache lines readers depend on (false sharing), but costs up to a few
+    /// hundred bytes per map and per read handle depending on the platform. If you create a large
+    /// number of small maps which are not read under heavy contention, disabling padding can
+    /// significantly reduce their memory footprint at the cost of read scalability.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::Builder;
+    ///
+    /// let (mut write, read) = Builder::new().without_cache_padding().build::<u32, u32>();
+    ///
+    /// write.guard().insert(1, 2);
+    /// assert_eq!(*read.guard().get(&1).unwrap(), 2);
+    /// ```
+    pub fn without_cache_padding(self) -> Self {
+        Self {
+            cache_padding: false,
+            ..self
+        }
+    }
+
+    /// Sets the strategy used to block the writer while it waits for readers to release their
+    /// guards. If not specified, the writer parks its thread via
+    /// [`thread::park`](std::thread::park).
+    ///
+    /// See [`WriterParker`](crate::WriterParker) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::{Builder, WriterParker};
+    /// use std::time::Duration;
+    ///
+    /// struct SpinParker;
+    ///
+    /// impl WriterParker for SpinParker {
+    ///     fn park(&self) {
+    ///         std::hint::spin_loop();
+    ///     }
+    ///
+    ///     fn park_timeout(&self, _timeout: Duration) {
+    ///         std::hint::spin_loop();
+    ///     }
+    ///
+    ///     fn unpark(&self) {}
+    /// }
+    ///
+    /// let (mut write, read) = Builder::new().with_parker(SpinParker).build::<u32, u32>();
+    ///
+    /// write.guard().insert(1, 2);
+    /// assert_eq!(*read.guard().get(&1).unwrap(), 2);
+    /// ```
+    pub fn with_parker<P>(self, parker: P) -> Self
+    where
+        P: WriterParker + 'static,
+    {
+        Self {
+            parker: Some(Arc::new(parker)),
+            ..self
+        }
+    }
+
+    /// Adds the map to the given [`Registry`](crate::Registry), so that it shares synchronization
+    /// infrastructure with the other maps in the registry.
+    ///
+    /// If the registry was created with a parker, then it replaces any parker set via
+    /// [`with_parker`](crate::Builder::with_parker). See [`Registry`](crate::Registry) for
+    /// details and examples.
+    pub fn in_registry(self, registry: &Registry) -> Self {
+        Self {
+            parker: registry.parker().or(self.parker),
+            registry: Some(registry.clone()),
+            ..self
         }
     }
 
+    /// Makes the map remember the order in which keys were inserted, so that its entries can be
+    /// iterated over in that order via [`View::iter_ordered`](crate::View::iter_ordered).
+    ///
+    /// Replacing the value of a key already in the map does not change its position, whereas
+    /// removing a key and inserting it again moves it to the end. Maintaining the order adds some
+    /// overhead to every publish, and a few words of memory per key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::Builder;
+    ///
+    /// let (mut write, read) = Builder::new().ordered().build::<u32, &str>();
+    ///
+    /// let mut guard = write.guard();
+    /// guard.insert(3, "c");
+    /// guard.insert(1, "a");
+    /// guard.insert(2, "b");
+    /// guard.publish();
+    ///
+    /// let guard = read.guard();
+    /// let keys = guard.iter_ordered().map(|(&key, _)| key).collect::<Vec<_>>();
+    /// assert_eq!(keys, [3, 1, 2]);
+    /// ```
+    pub fn ordered(self) -> Self {
+        Self {
+            ordered: true,
+            ..self
+        }
+    }
+
+    /// Makes the map assign a version to every entry, which changes whenever the entry is
+    /// inserted or its value is replaced. This enables optimistic updates via
+    /// [`View::get_versioned`](crate::View::get_versioned) and
+    /// [`View::replace_if_version`](crate::View::replace_if_version).
+    ///
+    /// Versions are unique across all entries for the lifetime of the map, so an entry which was
+    /// removed and inserted again never has the same version as before. Maintaining the versions
+    /// adds some overhead to every publish, and a few words of memory per key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::Builder;
+    ///
+    /// let (mut write, read) = Builder::new().versioned().build::<u32, u32>();
+    /// write.guard().insert(1, 10);
+    ///
+    /// // A reader prepares a change based on what it saw
+    /// let (value, version) = read.guard().get_versioned(&1).map(|(&v, ver)| (v, ver)).unwrap();
+    ///
+    /// // The writer only applies the change if the entry was not changed in the meantime
+    /// let mut guard = write.guard();
+    /// assert!(guard.replace_if_version(1, version, value + 1).is_ok());
+    /// assert!(guard.replace_if_version(1, version, value + 2).is_err());
+    /// ```
+    pub fn versioned(self) -> Self {
+        Self {
+            versioned: true,
+            ..self
+        }
+    }
+
+    /// Specializes the map for a single reader, which is common when one thread writes and
+    /// exactly one other thread reads.
+    ///
+    /// Normally every read handle registers itself with the map, and the writer has to lock the
+    /// list of registered handles on every publish. A single-reader map has exactly one read
+    /// handle, the one returned when the map is built, so publishing does not need to take any
+    /// locks. Creating another read handle panics, which includes cloning the read handle and
+    /// calling [`ReadHandle::guard_owned`](crate::ReadHandle::guard_owned).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::Builder;
+    /// use std::thread;
+    ///
+    /// let (mut write, read) = Builder::new().single_reader().build::<u32, u32>();
+    ///
+    /// let reader = thread::spawn(move || loop {
+    ///     if let Some(&value) = read.guard().get(&1) {
+    ///         return value;
+    ///     }
+    /// });
+    ///
+    /// write.guard().insert(1, 2);
+    /// assert_eq!(reader.join().unwrap(), 2);
+    /// ```
+    ///
+    /// ```should_panic
+    /// use flashmap::Builder;
+    ///
+    /// let (write, read) = Builder::new().single_reader().build::<u32, u32>();
+    /// let read2 = read.clone();
+    /// ```
+    pub fn single_reader(self) -> Self {
+        Self {
+            single_reader: true,
+            ..self
+        }
+    }
+
+    /// Sets the number of read handles the map is expected to have at once, so that room for
+    /// them can be reserved up front. If not specified, the default is the amount of parallelism
+    /// available to the program, as reported by
+    /// [`available_parallelism`](std::thread::available_parallelism).
+    ///
+    /// Registering more read handles than this is allowed, but may reallocate the list of read
+    /// handles, which happens while holding the lock the writer takes on every publish. This is
+    /// independent of [`with_capacity`](crate::Builder::with_capacity), and has no effect on
+    /// single-reader maps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::Builder;
+    ///
+    /// let (mut write, read) = Builder::new()
+    ///     .with_capacity(1000)
+    ///     .with_expected_readers(64)
+    ///     .build::<u32, u32>();
+    ///
+    /// let readers = read.clone_many(63);
+    /// write.guard().insert(1, 2);
+    /// assert!(readers.iter().all(|read| read.guard().get(&1) == Some(&2)));
+    /// ```
+    pub fn with_expected_readers(self, readers: usize) -> Self {
+        Self {
+            expected_readers: Some(readers),
+            ..self
+        }
+    }
+
+    /// Makes readers drop values evicted from the map as soon as the last reader viewing them
+    /// releases its guard, rather than leaving them for the writer to drop.
+    ///
+    /// Normally, values which were removed or replaced are dropped by the writer once it next
+    /// creates a write guard, or calls [`try_reclaim`](crate::WriteHandle::try_reclaim) or
+    /// [`flush`](crate::WriteHandle::flush). If writes are infrequent, this may keep large values
+    /// alive for a long time. With eager reclamation, publishing hands the evicted values over to
+    /// the readers, and whichever reader is the last to move off of the previous version of the
+    /// map drops them. If no reader is viewing the previous version when the changes are
+    /// published, then the writer drops them immediately instead.
+    ///
+    /// This means that evicted values may be dropped on reader threads. Working out which values
+    /// were evicted also adds a small amount of work to every publish, proportional to the number
+    /// of changes being published. Values removed by
+    /// [`clear_and_shrink`](crate::View::clear_and_shrink) or
+    /// [`replace_all`](crate::WriteHandle::replace_all) are still dropped by the writer as usual,
+    /// as are values whose [`Evicted`](crate::Evicted) is still alive when the changes are
+    /// published, since the value can still be read through it. Keys are always dropped by the writer, since it needs them to bring its copy of the map up
+    /// to date.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::Builder;
+    /// use std::sync::Arc;
+    ///
+    /// let (mut write, read) = Builder::new().eager_reclaim().build::<u32, Arc<()>>();
+    /// let value = Arc::new(());
+    /// write.guard().insert(1, Arc::clone(&value));
+    ///
+    /// let read_guard = read.guard();
+    /// write.guard().remove(1);
+    /// assert_eq!(Arc::strong_count(&value), 2);
+    ///
+    /// // The last reader viewing the removed value drops it, without any action from the writer
+    /// drop(read_guard);
+    /// assert_eq!(Arc::strong_count(&value), 1);
+    /// ```
+    pub fn eager_reclaim(self) -> Self {
+        Self {
+            eager_reclaim: true,
+            ..self
+        }
+    }
+
+    /// Sets the hasher for the underlying map to a deterministic
+    /// [`SeededState`](crate::SeededState) built from the given seed.
+    ///
+    /// Since the hashes produced depend only on the seed, maps built with the same seed will
+    /// iterate over the same entries in the same order. This is useful for reproducible tests and
+    /// benchmarks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::Builder;
+    ///
+    /// let (mut write1, read1) = Builder::new().with_seed(42).build::<u32, u32>();
+    /// let (mut write2, read2) = Builder::new().with_seed(42).build::<u32, u32>();
+    ///
+    /// let mut guard1 = write1.guard();
+    /// let mut guard2 = write2.guard();
+    /// for i in 0..100 {
+    ///     guard1.insert(i, i);
+    ///     guard2.insert(i, i);
+    /// }
+    /// guard1.publish();
+    /// guard2.publish();
+    ///
+    /// assert!(read1.guard().keys().eq(read2.guard().keys()));
+    /// ```
+    pub fn with_seed(self, seed: u64) -> Builder<SeededState> {
+        self.with_hasher_gen(HasherGen::Clone(SeededState::new(seed), SeededState::clone))
+    }
+
     /// Sets the hasher for the underlying map. The provided hasher must implement `Clone` due to
     /// the implementation details of this crate.
     ///
@@ -121,10 +518,7 @@ impl<S> Builder<S> {
     where
         H: Clone + BuildHasher,
     {
-        Builder {
-            capacity: self.capacity,
-            hasher: HasherGen::Clone(hasher, H::clone),
-        }
+        self.with_hasher_gen(HasherGen::Clone(hasher, H::clone))
     }
 
     /// Sets the hasher for the underlying map. Similar to
@@ -132,16 +526,64 @@ impl<S> Builder<S> {
     /// builder, the provided function will be called as many times as necessary to initialize
     /// the underlying map.
     ///
+    /// Since the function may capture state, this can be used to construct hashers which do not
+    /// implement `Clone`, such as those seeded from a captured value.
+    ///
     /// # Safety
     ///
     /// See [`crate::with_hasher`](crate::with_hasher).
-    pub unsafe fn with_hasher_generator<H>(self, gen: fn() -> H) -> Builder<H>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::Builder;
+    /// use std::hash::{BuildHasher, DefaultHasher, Hasher};
+    ///
+    /// // A hasher builder which cannot be cloned
+    /// struct Seeded(u64);
+    ///
+    /// impl BuildHasher for Seeded {
+    ///     type Hasher = DefaultHasher;
+    ///
+    ///     fn build_hasher(&self) -> DefaultHasher {
+    ///         let mut hasher = DefaultHasher::new();
+    ///         hasher.write_u64(self.0);
+    ///         hasher
+    ///     }
+    /// }
+    ///
+    /// let seed = 0x5eed;
+    /// let (mut write, read) = unsafe {
+    ///     Builder::new()
+    ///         .with_hasher_generator(move || Seeded(seed))
+    ///         .build::<u32, u32>()
+    /// };
+    ///
+    /// write.guard().insert(1, 2);
+    /// assert_eq!(*read.guard().get(&1).unwrap(), 2);
+    /// ```
+    pub unsafe fn with_hasher_generator<H, F>(self, gen: F) -> Builder<H>
     where
         H: BuildHasher,
+        F: Fn() -> H + Send + Sync + 'static,
     {
+        self.with_hasher_gen(HasherGen::Generate(Arc::new(gen)))
+    }
+
+    fn with_hasher_gen<H>(self, hasher: HasherGen<H>) -> Builder<H> {
         Builder {
             capacity: self.capacity,
-            hasher: HasherGen::Generate(gen),
+            hasher,
+            residual_overflow: self.residual_overflow,
+            cache_padding: self.cache_padding,
+            parker: self.parker,
+            registry: self.registry,
+            ordered: self.ordered,
+            versioned: self.versioned,
+            prefault: self.prefault,
+            single_reader: self.single_reader,
+            expected_readers: self.expected_readers,
+            eager_reclaim: self.eager_reclaim,
         }
     }
 
@@ -167,6 +609,78 @@ impl<S> Builder<S> {
         unsafe { self.build_assert_trusted() }
     }
 
+    /// Consumes the builder and returns a write handle and read handle to the map, after
+    /// performing some sanity checks on the key type and hasher in debug builds.
+    ///
+    /// A runtime check cannot prove that `Hash` and `Eq` are deterministic, but it can catch
+    /// blatant violations. With debug assertions enabled, `sample` is hashed twice with each of
+    /// the hashers used by the map, and compared against itself. If either hasher's hashes differ,
+    /// or if `sample` is not equal to itself, then this function panics. In release builds this
+    /// is identical to [`build`](crate::Builder::build).
+    ///
+    /// # Panics
+    ///
+    /// Panics in debug builds if the key type or hasher is detected to be nondeterministic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap::Builder;
+    /// let (mut write, read) = Builder::new().build_checked::<String, u32>(&"sample".to_owned());
+    ///
+    /// write.guard().insert("a".to_owned(), 1);
+    /// assert_eq!(*read.guard().get("a").unwrap(), 1);
+    /// ```
+    pub fn build_checked<K, V>(self, sample: &K) -> (WriteHandle<K, V, S>, ReadHandle<K, V, S>)
+    where
+        K: TrustedHashEq,
+        S: BuildHasher,
+    {
+        let args = self.into_args();
+
+        if cfg!(debug_assertions) {
+            // The two hashers may legitimately differ from each other, so each is only compared
+            // against itself
+            assert!(
+                [&args.h1, &args.h2]
+                    .into_iter()
+                    .all(|hasher| hasher.hash_one(sample) == hasher.hash_one(sample)),
+                "Key type or hasher produced different hashes for the same value"
+            );
+            assert!(
+                K::eq(sample, sample),
+                "Key type's Eq implementation is not reflexive"
+            );
+        }
+
+        unsafe { Core::build_map(args) }
+    }
+
+    /// Consumes the builder and returns the write and read handles to the map as a
+    /// [`Handles`](crate::Handles) struct.
+    ///
+    /// This is identical to [`build`](crate::Builder::build), except that the handles are
+    /// returned in named fields, which makes it harder to mix them up when destructuring.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::{Builder, Handles};
+    ///
+    /// let Handles { mut write, read } = Builder::new().build_named::<u32, u32>();
+    ///
+    /// write.guard().insert(10, 20);
+    /// assert_eq!(*read.guard().get(&10).unwrap(), 20);
+    /// ```
+    pub fn build_named<K, V>(self) -> Handles<K, V, S>
+    where
+        K: TrustedHashEq,
+        S: BuildHasher,
+    {
+        let (write, read) = self.build();
+        Handles { write, read }
+    }
+
     /// Consumes the builder and returns a write handle and read handle to the map.
     ///
     /// # Safety
@@ -187,13 +701,92 @@ impl<S> Builder<S> {
             capacity: self.capacity,
             h1,
             h2,
+            residual_overflow: self.residual_overflow,
+            cache_padding: self.cache_padding,
+            parker: self.parker,
+            registry: self.registry,
+            ordered: self.ordered,
+            versioned: self.versioned,
+            prefault: self.prefault,
+            single_reader: self.single_reader,
+            expected_readers: self.expected_readers,
+            eager_reclaim: self.eager_reclaim,
         }
     }
 }
 
-#[derive(Clone, Copy)]
+/// The write and read handles to a map, as returned by
+/// [`Builder::build_named`](crate::Builder::build_named).
+pub struct Handles<K, V, S = RandomState>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// The write handle to the map.
+    pub write: WriteHandle<K, V, S>,
+    /// The read handle to the map.
+    pub read: ReadHandle<K, V, S>,
+}
+
+/// Determines what happens when the residual reader count overflows during a publish.
+///
+/// When changes are published, the writer sums up the number of read guards which are still
+/// viewing the old version of the map. This sum is stored in an `isize`, so if more than
+/// `isize::MAX` guards are outstanding across all read handles, it cannot be represented. This
+/// can only happen if guards are leaked (e.g. via [`mem::forget`](std::mem::forget)) on a massive
+/// scale, so it almost certainly indicates a bug in the calling program.
+///
+/// By default the process is aborted. Panicking is not an option since the overflow is detected
+/// while the maps are being swapped, and unwinding at that point would leave the map in an
+/// inconsistent state. Continuing without an accurate count would allow the writer to modify a
+/// map that readers may still be viewing, which is undefined behavior. Waiting for the readers
+/// counted so far is not an option either: by the time the overflow is detected, the readers of
+/// the guards which could not be counted may already be releasing them.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum ResidualOverflow {
+    /// Abort the process. This is the default.
+    #[default]
+    Abort,
+    /// Call the given function, then abort the process. This is useful for logging or flushing
+    /// state before the process dies. The function must not panic or access the map.
+    AbortWith(fn()),
+}
+
+/// A strategy for blocking the writer while it waits for readers to release their guards.
+///
+/// When changes are published, the writer may need to wait for readers still viewing the old
+/// version of the map before it can modify that version again. By default this is done by parking
+/// the writer's thread, and having the last such reader unpark it. Implementing this trait allows
+/// that wait to be integrated with other blocking primitives, such as those of a custom runtime.
+///
+/// Implementations must behave like a binary semaphore with at most one permit: a call to
+/// [`unpark`](crate::WriterParker::unpark) which happens before the corresponding call to
+/// [`park`](crate::WriterParker::park) must cause that call to `park` to return immediately.
+/// Spurious wakeups are permitted, since the writer always re-checks whether it can proceed.
+///
+/// [`unpark`](crate::WriterParker::unpark) is called from reader threads and must not access the
+/// map. Moreover, it runs while a read guard is being dropped, so it should be cheap.
+pub trait WriterParker: Send + Sync {
+    /// Blocks the writer until [`unpark`](crate::WriterParker::unpark) is called, or returns
+    /// immediately if it was already called since the last time this function returned.
+    fn park(&self);
+
+    /// Like [`park`](crate::WriterParker::park), but blocks the writer for at most `timeout`.
+    ///
+    /// This is used by [`synchronize_timeout`](crate::WriteHandle::synchronize_timeout), which
+    /// re-checks whether it can proceed after every call, so returning early is permitted. Never
+    /// blocking at all makes the writer spin until the timeout elapses.
+    fn park_timeout(&self, timeout: Duration);
+
+    /// Wakes up the writer if it is currently blocked in
+    /// [`park`](crate::WriterParker::park), otherwise causes the next call to `park` to return
+    /// immediately.
+    fn unpark(&self);
+}
+
+#[derive(Clone)]
 enum HasherGen<S> {
-    Generate(fn() -> S),
+    Generate(Arc<dyn Fn() -> S + Send + Sync>),
     MakeBoth(fn() -> (S, S)),
     Clone(S, fn(&S) -> S),
 }
@@ -212,6 +805,16 @@ pub(crate) struct BuilderArgs<S> {
     pub capacity: usize,
     pub h1: S,
     pub h2: S,
+    pub residual_overflow: ResidualOverflow,
+    pub cache_padding: bool,
+    pub parker: Option<Arc<dyn WriterParker>>,
+    pub registry: Option<Registry>,
+    pub ordered: bool,
+    pub versioned: bool,
+    pub prefault: bool,
+    pub single_reader: bool,
+    pub expected_readers: Option<usize>,
+    pub eager_reclaim: bool,
 }
 
 /// ```compile_fail
@@ -242,6 +845,67 @@ struct NotSendTypes;
 #[allow(dead_code)]
 struct NotSyncTypes;
 
+// An evicted value is only dropped when the operation log is flushed, which requires mutable
+// access to the write handle. Un-leaked evicted values must therefore keep the handle borrowed.
+
+/// ```compile_fail
+/// let (mut write, _read) = flashmap::new::<u32, u32>();
+/// let mut guard = write.guard();
+/// guard.insert(0, 0);
+/// let evicted = guard.insert(0, 1).unwrap();
+/// drop(guard);
+/// drop(write.guard());
+/// assert_eq!(*evicted, 0);
+/// ```
+///
+/// ```compile_fail
+/// let (mut write, _read) = flashmap::new::<u32, u32>();
+/// let mut guard = write.guard();
+/// guard.insert(0, 0);
+/// let evicted = guard.remove(0).unwrap();
+/// drop(guard);
+/// write.flush();
+/// assert_eq!(*evicted, 0);
+/// ```
+///
+/// ```compile_fail
+/// let (mut write, _read) = flashmap::new::<u32, u32>();
+/// let mut guard = write.guard();
+/// guard.insert(0, 0);
+/// let evicted = guard.remove(0).unwrap();
+/// drop(guard);
+/// drop(write);
+/// assert_eq!(*evicted, 0);
+/// ```
+///
+/// ```compile_fail
+/// let (mut write, _read) = flashmap::new::<u32, u32>();
+/// let mut batch = write.batch();
+/// let mut guard = batch.guard();
+/// guard.insert(0, 0);
+/// let evicted = guard.remove(0).unwrap();
+/// drop(guard);
+/// drop(batch);
+/// assert_eq!(*evicted, 0);
+/// ```
+#[allow(dead_code)]
+struct EvictedBorrowsHandle;
+
+// The design assumes that there is only ever one write handle per map. Write handles are only
+// created alongside the map by `Builder`, and cannot be cloned.
+
+/// ```compile_fail
+/// fn assert_clone<T: Clone>() {}
+/// assert_clone::<flashmap::WriteHandle<u32, u32>>();
+/// ```
+///
+/// ```compile_fail
+/// let (write, _read) = flashmap::new::<u32, u32>();
+/// let _second = write.clone();
+/// ```
+#[allow(dead_code)]
+struct WriteHandleNotClone;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -276,13 +940,19 @@ mod tests {
         assert_send::<ReadHandle<SendSync, SendSync, SendSync>>();
         assert_send::<WriteHandle<SendSync, SendSync, SendSync>>();
         assert_send::<View<ReadGuard<'_, SendSync, SendSync, SendSync>>>();
+        assert_send::<View<OwnedReadGuard<SendSync, SendSync, SendSync>>>();
+        assert_send::<CachedReadGuard<'_, SendSync, SendSync, SendSync>>();
         assert_send::<Leaked<SendOnly>>();
+        assert_send::<Builder<SendSync>>();
     }
 
     #[test]
     fn sync_types() {
         assert_sync::<ReadHandle<SendSync, SendSync, SendSync>>();
         assert_sync::<View<ReadGuard<'_, SendSync, SendSync, SendSync>>>();
+        assert_sync::<View<OwnedReadGuard<SendSync, SendSync, SendSync>>>();
+        assert_sync::<CachedReadGuard<'_, SendSync, SendSync, SendSync>>();
         assert_sync::<Leaked<SyncOnly>>();
+        assert_sync::<Builder<SendSync>>();
     }
 }
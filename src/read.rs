@@ -1,4 +1,11 @@
-use std::{collections::hash_map::RandomState, ptr::NonNull};
+use std::{
+    borrow::Borrow,
+    cell::RefCell,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    ops::Deref,
+    ptr::NonNull,
+};
 
 use crate::{
     core::{Core, MapIndex, RefCount, SharedMapAccess},
@@ -6,9 +13,13 @@ use crate::{
     loom::sync::Arc,
     util::unlikely,
     view::sealed::ReadAccess,
+    write::{get_versioned, iter_ordered, EntryVersions, InsertionOrder},
     Map, View,
 };
 
+const NOT_ORDERED: &str = "Map was not built with Builder::ordered";
+const NOT_VERSIONED: &str = "Map was not built with Builder::versioned";
+
 /// A read handle for the map.
 ///
 /// This type allows for the creation of [`ReadGuard`s](crate::ReadGuard), which provide immutable
@@ -102,6 +113,252 @@ impl<K, V, S> ReadHandle<K, V, S> {
             map_index,
         })
     }
+
+    /// Creates a new [`OwnedReadGuard`](crate::OwnedReadGuard) wrapped in a
+    /// [`View`](crate::View). Unlike [`guard`](crate::ReadHandle::guard), the returned guard does
+    /// not borrow this handle, so it can be stored or returned independently of it.
+    ///
+    /// The owned guard registers itself as a new reader of the map, so creating one is about as
+    /// expensive as [`clone`](Clone::clone)ing this handle. Prefer `guard` where possible.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use flashmap::{OwnedReadGuard, View};
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    /// write.guard().insert(1, 2);
+    ///
+    /// let guard: View<OwnedReadGuard<u32, u32>> = read.guard_owned();
+    ///
+    /// // The guard may outlive the handle which created it
+    /// drop(read);
+    /// assert_eq!(*guard.get(&1).unwrap(), 2);
+    /// ```
+    pub fn guard_owned(&self) -> View<OwnedReadGuard<K, V, S>> {
+        let handle = self.clone();
+        let map_index = unsafe { handle.refcount.as_ref() }.increment();
+
+        View::new(OwnedReadGuard { handle, map_index })
+    }
+
+    /// Creates a new [`TrackedReadGuard`](crate::TrackedReadGuard), which behaves like a regular
+    /// read guard but records every key it is asked to look up.
+    ///
+    /// The recorded keys can be retrieved via
+    /// [`into_read_set`](crate::TrackedReadGuard::into_read_set), which is useful for determining
+    /// which entries a computation depended on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<String, u32>();
+    /// write.guard().insert("a".to_owned(), 1);
+    ///
+    /// let guard = read.tracked_guard();
+    /// assert_eq!(guard.get("a"), Some(&1));
+    /// assert!(!guard.contains_key("b"));
+    ///
+    /// // Keys which were not present are recorded as well
+    /// assert_eq!(guard.into_read_set(), ["a", "b"]);
+    /// ```
+    #[inline]
+    pub fn tracked_guard(&self) -> TrackedReadGuard<'_, K, V, S> {
+        TrackedReadGuard {
+            view: self.guard(),
+            read_set: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Creates a [`Latest`](crate::Latest) accessor, which creates a fresh guard for every
+    /// operation and therefore always observes the most recently published version of the map.
+    ///
+    /// This spares the caller from managing guards at the cost of creating a guard per call, and
+    /// of not being able to borrow from the map. Prefer [`guard`](crate::ReadHandle::guard) when
+    /// performing several reads which should observe the same snapshot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<String, u32>();
+    /// let latest = read.latest();
+    ///
+    /// assert!(latest.is_empty());
+    ///
+    /// write.guard().insert("ferris".to_owned(), 7);
+    ///
+    /// // No need to create a new guard to see the write
+    /// assert_eq!(latest.get("ferris"), Some(7));
+    /// assert!(latest.contains_key("ferris"));
+    /// assert_eq!(latest.len(), 1);
+    /// ```
+    #[inline]
+    pub fn latest(&self) -> Latest<'_, K, V, S> {
+        Latest { handle: self }
+    }
+
+    /// Creates a [`CachedReadGuard`](crate::CachedReadGuard), a long-lived guard which is only
+    /// replaced once the writer publishes changes.
+    ///
+    /// Creating and dropping a guard costs one atomic read-modify-write operation each. On very
+    /// hot read paths, a cached guard can be kept around instead (for instance in a thread-local
+    /// or in the state of a worker loop) and [`refresh`](crate::CachedReadGuard::refresh)ed
+    /// periodically, which only costs an atomic load unless the writer published something.
+    ///
+    /// The tradeoff is staleness: a cached guard keeps viewing the version of the map which was
+    /// published when it was last refreshed, and since it pins that version, the writer cannot
+    /// create a new write guard until every cached guard viewing an outdated version is refreshed
+    /// or dropped. Cached guards should therefore be refreshed regularly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use std::thread;
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    /// write.guard().insert(0, 0);
+    ///
+    /// let reader = thread::spawn(move || {
+    ///     let mut guard = read.guard_cached();
+    ///     loop {
+    ///         // Picks up new versions of the map without creating a guard per iteration
+    ///         guard.refresh();
+    ///         if guard.get(&0) == Some(&1) {
+    ///             break;
+    ///         }
+    ///     }
+    /// });
+    ///
+    /// write.guard().insert(0, 1);
+    /// reader.join().unwrap();
+    /// ```
+    #[inline]
+    pub fn guard_cached(&self) -> CachedReadGuard<'_, K, V, S> {
+        CachedReadGuard {
+            handle: self,
+            view: self.guard(),
+        }
+    }
+
+    /// Creates `count` new read handles to the same map.
+    ///
+    /// This is equivalent to [`clone`](Clone::clone)ing this handle `count` times, but all of the
+    /// new handles are registered at once, which reduces contention when fanning out to many
+    /// reader threads. Each returned handle is fully independent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use std::thread;
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    /// write.guard().insert(1, 2);
+    ///
+    /// let threads = read
+    ///     .clone_many(4)
+    ///     .into_iter()
+    ///     .map(|read| thread::spawn(move || assert_eq!(*read.guard().get(&1).unwrap(), 2)))
+    ///     .collect::<Vec<_>>();
+    ///
+    /// for thread in threads {
+    ///     thread.join().unwrap();
+    /// }
+    /// ```
+    pub fn clone_many(&self, count: usize) -> Vec<Self> {
+        Core::new_readers(Arc::clone(&self.core), count)
+    }
+
+    /// Creates a guard, and calls the given function with it, returning the function's result along
+    /// with the version of the snapshot it observed.
+    ///
+    /// The version starts at zero and increases by one every time the writer publishes changes,
+    /// so two calls which return the same version observed exactly the same contents. This makes
+    /// it cheap for a cache layered on top of the map to tell whether a previously computed
+    /// result is still up to date.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    /// write.guard().insert(1, 2);
+    ///
+    /// let (sum, version) = read.read(|guard| guard.values().sum::<u32>());
+    /// assert_eq!(sum, 2);
+    ///
+    /// // Publishing without changes does not create a new version
+    /// write.guard();
+    /// assert_eq!(read.read(|_| ()).1, version);
+    ///
+    /// write.guard().insert(3, 4);
+    /// let (sum, new_version) = read.read(|guard| guard.values().sum::<u32>());
+    /// assert_eq!(sum, 6);
+    /// assert_eq!(new_version, version + 1);
+    /// ```
+    pub fn read<F, R>(&self, f: F) -> (R, u64)
+    where
+        F: FnOnce(&View<ReadGuard<'_, K, V, S>>) -> R,
+    {
+        let guard = self.guard();
+        let version = guard.snapshot_version();
+        (f(&guard), version)
+    }
+
+    /// Returns whether or not this handle and the given handle read from the same map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (_write, read) = flashmap::new::<u32, u32>();
+    /// let (_other_write, other_read) = flashmap::new::<u32, u32>();
+    ///
+    /// assert!(read.same_map(&read.clone()));
+    /// assert!(!read.same_map(&other_read));
+    /// ```
+    #[inline]
+    pub fn same_map(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.core, &other.core)
+    }
+
+    #[inline]
+    pub(crate) fn reads_from(&self, core: &Arc<Core<K, V, S>>) -> bool {
+        Arc::ptr_eq(&self.core, core)
+    }
+
+    #[inline]
+    fn is_stale(&self, map_index: MapIndex) -> bool {
+        unsafe { self.refcount.as_ref() }.map_index() != map_index
+    }
+
+    #[inline]
+    fn insertion_order(&self, map_index: MapIndex) -> &InsertionOrder<K> {
+        let order = self.core.insertion_order(map_index).expect(NOT_ORDERED);
+        // Safety: the order is only modified by the writer while no readers view the
+        // corresponding map, and we hold a guard for that map
+        order.with(|order_ptr| unsafe { &*order_ptr })
+    }
+
+    #[inline]
+    fn entry_versions(&self, map_index: MapIndex) -> &EntryVersions<K> {
+        let versions = self.core.entry_versions(map_index).expect(NOT_VERSIONED);
+        // Safety: see insertion_order
+        versions.with(|versions_ptr| unsafe { &*versions_ptr })
+    }
+
+    #[inline]
+    fn release(&self, map_index: MapIndex) {
+        let current_reader_map = unsafe { self.refcount.as_ref() }.decrement();
+
+        if unlikely(current_reader_map != map_index) {
+            unsafe { self.core.release_residual() };
+        }
+    }
 }
 
 impl<K, V, S> Clone for ReadHandle<K, V, S> {
@@ -155,13 +412,259 @@ impl<'guard, K, V, S> ReadAccess for ReadGuard<'guard, K, V, S> {
     }
 }
 
+impl<'guard, K, V, S> ReadGuard<'guard, K, V, S> {
+    #[inline]
+    pub(crate) fn is_stale(&self) -> bool {
+        self.handle.is_stale(self.map_index)
+    }
+
+    #[inline]
+    pub(crate) fn snapshot_version(&self) -> u64 {
+        self.handle.core.snapshot_version(self.map_index)
+    }
+
+    #[inline]
+    pub(crate) fn iter_ordered(&self) -> impl Iterator<Item = (&K, &V)>
+    where
+        K: Hash + Eq,
+        S: BuildHasher,
+    {
+        let order = self.handle.insertion_order(self.map_index);
+        self.with_map(|map| iter_ordered(map, order))
+    }
+
+    #[inline]
+    pub(crate) fn get_versioned<Q>(&self, key: &Q) -> Option<(&V, u64)>
+    where
+        K: Hash + Eq + Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        S: BuildHasher,
+    {
+        let versions = self.handle.entry_versions(self.map_index);
+        self.with_map(|map| get_versioned(map, versions, key))
+    }
+}
+
 impl<'guard, K, V, S> Drop for ReadGuard<'guard, K, V, S> {
     #[inline]
     fn drop(&mut self) {
-        let current_reader_map = unsafe { self.handle.refcount.as_ref() }.decrement();
+        self.handle.release(self.map_index);
+    }
+}
+
+/// Provides immutable access to the map, and prevents entries from being dropped. This guard does
+/// not borrow the handle that created it.
+///
+/// This guard behaves exactly like a [`ReadGuard`](crate::ReadGuard), but holds its own reference
+/// to the map rather than borrowing a read handle. See
+/// [`ReadHandle::guard_owned`](crate::ReadHandle::guard_owned) for examples.
+pub struct OwnedReadGuard<K, V, S = RandomState> {
+    handle: ReadHandle<K, V, S>,
+    map_index: MapIndex,
+}
+
+impl<K, V, S> ReadAccess for OwnedReadGuard<K, V, S> {
+    type Map = Map<K, V, S>;
+
+    #[inline]
+    fn with_map<'read, F, R>(&'read self, op: F) -> R
+    where
+        F: FnOnce(&'read Self::Map) -> R,
+    {
+        unsafe { self.handle.map_access.get(self.map_index) }.with(|ptr| op(unsafe { &*ptr }))
+    }
+}
+
+impl<K, V, S> OwnedReadGuard<K, V, S> {
+    #[inline]
+    pub(crate) fn is_stale(&self) -> bool {
+        self.handle.is_stale(self.map_index)
+    }
+
+    #[inline]
+    pub(crate) fn iter_ordered(&self) -> impl Iterator<Item = (&K, &V)>
+    where
+        K: Hash + Eq,
+        S: BuildHasher,
+    {
+        let order = self.handle.insertion_order(self.map_index);
+        self.with_map(|map| iter_ordered(map, order))
+    }
+
+    #[inline]
+    pub(crate) fn get_versioned<Q>(&self, key: &Q) -> Option<(&V, u64)>
+    where
+        K: Hash + Eq + Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        S: BuildHasher,
+    {
+        let versions = self.handle.entry_versions(self.map_index);
+        self.with_map(|map| get_versioned(map, versions, key))
+    }
+}
+
+impl<K, V, S> Drop for OwnedReadGuard<K, V, S> {
+    #[inline]
+    fn drop(&mut self) {
+        self.handle.release(self.map_index);
+    }
+}
+
+/// A read guard which records the keys that are looked up through it.
+///
+/// Only [`get`](crate::TrackedReadGuard::get) and
+/// [`contains_key`](crate::TrackedReadGuard::contains_key) are exposed, since other accessors such
+/// as iteration cannot be attributed to individual keys. See
+/// [`ReadHandle::tracked_guard`](crate::ReadHandle::tracked_guard) for examples.
+pub struct TrackedReadGuard<'guard, K, V, S = RandomState> {
+    view: View<ReadGuard<'guard, K, V, S>>,
+    read_set: RefCell<Vec<K>>,
+}
+
+impl<'guard, K, V, S> TrackedReadGuard<'guard, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns a reference to the value corresponding to the key, recording the key in the read
+    /// set. See [`View::get`](crate::View::get).
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        self.record(key);
+        self.view.get(key)
+    }
 
-        if unlikely(current_reader_map != self.map_index) {
-            unsafe { self.handle.core.release_residual() };
+    /// Returns whether or not the map contains the given key, recording the key in the read set.
+    /// See [`View::contains_key`](crate::View::contains_key).
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        self.record(key);
+        self.view.contains_key(key)
+    }
+
+    #[inline]
+    fn record<Q>(&self, key: &Q)
+    where
+        Q: ToOwned<Owned = K> + ?Sized,
+    {
+        self.read_set.borrow_mut().push(key.to_owned());
+    }
+}
+
+impl<'guard, K, V, S> TrackedReadGuard<'guard, K, V, S> {
+    /// Releases the guard and returns every key which was looked up through it, in the order the
+    /// lookups occurred. Keys looked up multiple times appear multiple times.
+    #[inline]
+    pub fn into_read_set(self) -> Vec<K> {
+        self.read_set.into_inner()
+    }
+}
+
+/// A read guard which is only replaced once the writer publishes changes.
+///
+/// This dereferences to a regular read guard, and pins the version of the map it views until it
+/// is [`refresh`](crate::CachedReadGuard::refresh)ed or dropped. See
+/// [`ReadHandle::guard_cached`](crate::ReadHandle::guard_cached) for details.
+pub struct CachedReadGuard<'handle, K, V, S = RandomState> {
+    handle: &'handle ReadHandle<K, V, S>,
+    view: View<ReadGuard<'handle, K, V, S>>,
+}
+
+impl<'handle, K, V, S> CachedReadGuard<'handle, K, V, S> {
+    /// Makes this guard view the most recently published version of the map, releasing the
+    /// version it viewed before. Returns whether or not the guard was replaced.
+    ///
+    /// If the writer did not publish anything since this guard was last refreshed, this does not
+    /// touch the reference count at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
+    /// let mut guard = read.guard_cached();
+    /// assert!(!guard.refresh());
+    ///
+    /// write.guard().insert(1, 2);
+    /// assert!(guard.get(&1).is_none());
+    ///
+    /// assert!(guard.refresh());
+    /// assert_eq!(guard.get(&1), Some(&2));
+    /// ```
+    #[inline]
+    pub fn refresh(&mut self) -> bool {
+        if !self.view.is_stale() {
+            return false;
         }
+
+        self.view = self.handle.guard();
+        true
+    }
+}
+
+impl<'handle, K, V, S> Deref for CachedReadGuard<'handle, K, V, S> {
+    type Target = View<ReadGuard<'handle, K, V, S>>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.view
+    }
+}
+
+/// Provides access to the most recently published version of the map, without having to manage
+/// guards.
+///
+/// Every method creates and releases its own guard, so consecutive calls may observe different
+/// versions of the map. See [`ReadHandle::latest`](crate::ReadHandle::latest) for examples.
+pub struct Latest<'handle, K, V, S = RandomState> {
+    handle: &'handle ReadHandle<K, V, S>,
+}
+
+impl<'handle, K, V, S> Latest<'handle, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns a clone of the value corresponding to the key. See [`View::get`](crate::View::get).
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.handle.guard().get_cloned(key)
+    }
+
+    /// Returns whether or not the map contains the given key. See
+    /// [`View::contains_key`](crate::View::contains_key).
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.handle.guard().contains_key(key)
+    }
+
+    /// Returns the length of the map. See [`View::len`](crate::View::len).
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.handle.guard().len()
+    }
+
+    /// Returns whether or not the map is empty. See [`View::is_empty`](crate::View::is_empty).
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.handle.guard().is_empty()
     }
 }
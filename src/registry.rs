@@ -0,0 +1,110 @@
+use std::sync::{atomic::Ordering, Arc};
+
+#[cfg(feature = "portable-atomic")]
+use portable_atomic::AtomicUsize;
+#[cfg(not(feature = "portable-atomic"))]
+use std::sync::atomic::AtomicUsize;
+
+use crate::WriterParker;
+
+/// A group of maps which share their synchronization infrastructure.
+///
+/// Maps can be added to a registry via [`Builder::in_registry`](crate::Builder::in_registry).
+/// Every map built this way uses the registry's [`WriterParker`](crate::WriterParker), if one was
+/// provided, rather than a parker of its own, and is counted by
+/// [`map_count`](crate::Registry::map_count) for as long as it is alive.
+///
+/// Note that this crate does not run any background threads, so the per-map state which is
+/// shared here is limited to the parker.
+///
+/// # Examples
+///
+/// ```
+/// use flashmap::{Builder, Registry};
+///
+/// let registry = Registry::new();
+///
+/// let (mut write1, read1) = Builder::new().in_registry(&registry).build::<u32, u32>();
+/// let (mut write2, read2) = Builder::new().in_registry(&registry).build::<u32, String>();
+/// assert_eq!(registry.map_count(), 2);
+///
+/// write1.guard().insert(1, 1);
+/// write2.guard().insert(2, "two".to_owned());
+///
+/// drop((write1, read1));
+/// assert_eq!(registry.map_count(), 1);
+/// ```
+#[derive(Clone)]
+pub struct Registry {
+    inner: Arc<RegistryInner>,
+}
+
+struct RegistryInner {
+    parker: Option<Arc<dyn WriterParker>>,
+    maps: AtomicUsize,
+}
+
+impl Registry {
+    /// Creates a new registry whose maps park their writers via
+    /// [`thread::park`](std::thread::park).
+    pub fn new() -> Self {
+        Self::with_inner(None)
+    }
+
+    /// Creates a new registry whose maps all share the given parker.
+    ///
+    /// Since the parker is shared, [`unpark`](crate::WriterParker::unpark) may be called on behalf
+    /// of any map in the registry, and several writers may be blocked in
+    /// [`park`](crate::WriterParker::park) at once. Every call to `unpark` must therefore wake
+    /// all writers which are currently parked, and must not allow one writer to consume a wakeup
+    /// meant for another. Spurious wakeups are always permitted.
+    pub fn with_parker<P>(parker: P) -> Self
+    where
+        P: WriterParker + 'static,
+    {
+        Self::with_inner(Some(Arc::new(parker)))
+    }
+
+    fn with_inner(parker: Option<Arc<dyn WriterParker>>) -> Self {
+        Self {
+            inner: Arc::new(RegistryInner {
+                parker,
+                maps: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Returns the number of maps in this registry which have not yet been deallocated. A map is
+    /// deallocated once its write handle and all of its read handles and guards are dropped.
+    pub fn map_count(&self) -> usize {
+        self.inner.maps.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn parker(&self) -> Option<Arc<dyn WriterParker>> {
+        self.inner.parker.clone()
+    }
+
+    pub(crate) fn join(&self) -> Membership {
+        self.inner.maps.fetch_add(1, Ordering::Relaxed);
+        Membership {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps a map counted in its registry until it is dropped.
+pub(crate) struct Membership {
+    inner: Arc<RegistryInner>,
+}
+
+impl Drop for Membership {
+    fn drop(&mut self) {
+        self.inner.maps.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,127 @@
+//! Streaming (de)serialization of maps, available with the `serde_json` feature.
+
+use std::{fmt, hash::BuildHasher, io, marker::PhantomData};
+
+use serde::{
+    de::{DeserializeOwned, SeqAccess, Visitor},
+    Deserializer, Serialize, Serializer,
+};
+
+use crate::{view::sealed::ReadAccess, Builder, Map, ReadHandle, TrustedHashEq, View, WriteHandle};
+
+/// Reads entries in the format written by [`View::serialize_to`](crate::View::serialize_to) from
+/// the given reader, and inserts them into a new map.
+///
+/// Entries are inserted as they are read, without building an intermediate collection, and are
+/// published all at once at the end. If the format provides the number of entries up front, the
+/// map is pre-sized accordingly. If a key appears more than once, the last value wins. The reader
+/// is not buffered, so wrapping it in a [`BufReader`](std::io::BufReader) is recommended.
+///
+/// This function is only available with the `serde_json` feature enabled.
+///
+/// # Examples
+///
+/// ```
+/// # use flashmap;
+/// let input = br#"[["ferris",7],["corro",3]]"#;
+/// let (write, read) = flashmap::deserialize_from::<_, String, u32>(&input[..]).unwrap();
+///
+/// let guard = read.guard();
+/// assert_eq!(guard.len(), 2);
+/// assert_eq!(*guard.get("ferris").unwrap(), 7);
+/// ```
+pub fn deserialize_from<R, K, V>(reader: R) -> io::Result<(WriteHandle<K, V>, ReadHandle<K, V>)>
+where
+    R: io::Read,
+    K: TrustedHashEq + DeserializeOwned,
+    V: DeserializeOwned,
+{
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let handles = deserializer.deserialize_seq(LoadVisitor(PhantomData))?;
+    deserializer.end()?;
+    Ok(handles)
+}
+
+struct LoadVisitor<K, V>(PhantomData<fn() -> (K, V)>);
+
+impl<'de, K, V> Visitor<'de> for LoadVisitor<K, V>
+where
+    K: TrustedHashEq + DeserializeOwned,
+    V: DeserializeOwned,
+{
+    type Value = (WriteHandle<K, V>, ReadHandle<K, V>);
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence of key-value pairs")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let (mut write, read) = Builder::new()
+            .with_capacity(seq.size_hint().unwrap_or(0))
+            .build();
+
+        let mut guard = write.guard();
+        while let Some((key, value)) = seq.next_element()? {
+            guard.insert(key, value);
+        }
+        guard.publish();
+
+        Ok((write, read))
+    }
+}
+
+impl<K, V, S, G> View<G>
+where
+    G: ReadAccess<Map = Map<K, V, S>>,
+    S: BuildHasher,
+{
+    /// Writes every entry of the map to the given writer as JSON, without building an
+    /// intermediate collection.
+    ///
+    /// The entries are written as an array of `[key, value]` pairs, in arbitrary order. Since
+    /// the guard keeps its snapshot of the map alive, the output is consistent even if the writer
+    /// publishes changes while this function is running. The writer is not buffered, so wrapping
+    /// it in a [`BufWriter`](std::io::BufWriter) is recommended.
+    ///
+    /// This method is only available with the `serde_json` feature enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<String, u32>();
+    /// write.guard().insert("ferris".to_owned(), 7);
+    ///
+    /// let mut output = Vec::new();
+    /// read.guard().serialize_to(&mut output).unwrap();
+    /// assert_eq!(output, br#"[["ferris",7]]"#);
+    /// ```
+    pub fn serialize_to<W>(&self, writer: W) -> io::Result<()>
+    where
+        W: io::Write,
+        K: Serialize,
+        V: Serialize,
+    {
+        serde_json::to_writer(writer, &Entries(self)).map_err(io::Error::from)
+    }
+}
+
+struct Entries<'a, G>(&'a View<G>);
+
+impl<K, V, S, G> Serialize for Entries<'_, G>
+where
+    G: ReadAccess<Map = Map<K, V, S>>,
+    S: BuildHasher,
+    K: Serialize,
+    V: Serialize,
+{
+    fn serialize<Z>(&self, serializer: Z) -> Result<Z::Ok, Z::Error>
+    where
+        Z: Serializer,
+    {
+        serializer.collect_seq(self.0.iter())
+    }
+}
@@ -306,6 +306,43 @@ impl<T> Alias<T> {
     }
 }
 
+impl<T> Alias<T>
+where
+    T: Deref,
+{
+    /// Returns whether or not the two given aliases point to the same memory, which is the case
+    /// for any two members of the same alias family.
+    ///
+    /// An alias only holds a shallow copy of its value, so this compares the locations of the
+    /// values the aliases dereference to, such as the allocation behind a `Box` or `String`. This
+    /// is meant as a diagnostic aid; values which do not own any memory, such as empty strings,
+    /// may point to the same (dangling) location without being in the same alias family.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap::Alias;
+    /// let mut a = Alias::new(Box::new(10));
+    /// // Safety: the data aliased by `a` is not currently being modified
+    /// let b = unsafe { Alias::copy(&a) };
+    /// let mut c = Alias::new(Box::new(10));
+    ///
+    /// assert!(Alias::same_family(&a, &b));
+    /// // Equal values, but different objects in memory
+    /// assert!(!Alias::same_family(&a, &c));
+    ///
+    /// // Safety: none of these aliases are accessed after this point
+    /// unsafe {
+    ///     Alias::drop(&mut a);
+    ///     Alias::drop(&mut c);
+    /// }
+    /// ```
+    #[inline]
+    pub fn same_family(a: &Self, b: &Self) -> bool {
+        ptr::eq::<T::Target>(&***a, &***b)
+    }
+}
+
 impl<T> Deref for Alias<T> {
     type Target = T;
 
@@ -4,6 +4,9 @@
 use std::ops::{Deref, DerefMut};
 
 #[derive(Clone, Copy, Default, Hash, PartialEq, Eq)]
+// Modified from the original: repr(C) guarantees that the value is stored at offset zero, so
+// pointers to a padded value can be cast to and from pointers to the value itself.
+#[repr(C)]
 // Starting from Intel's Sandy Bridge, spatial prefetcher is now pulling pairs of 64-byte cache
 // lines at a time, so we have to align to 128 bytes rather than 64.
 //
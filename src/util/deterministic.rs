@@ -4,7 +4,10 @@ use std::hash::Hash;
 /// [`Eq`](std::cmp::Eq) implementation.
 ///
 /// This trait is implemented for all standard library types which have deterministic `Hash` and
-/// `Eq` implementations.
+/// `Eq` implementations. Implementations for the date and time types of
+/// [`chrono`](https://crates.io/crates/chrono) are available behind the `chrono` feature, and
+/// likewise for [`smol_str`](https://crates.io/crates/smol_str) and
+/// [`uuid`](https://crates.io/crates/uuid).
 ///
 /// # Safety
 ///
@@ -16,15 +19,79 @@ use std::hash::Hash;
 /// result of comparing it to another constant will not change.
 pub unsafe trait TrustedHashEq: Hash + Eq {}
 
+/// Fails to compile unless `T` implements [`TrustedHashEq`](crate::TrustedHashEq).
+///
+/// This function does nothing at runtime. It is meant to be used as documentation and as a
+/// tripwire in code which relies on a type being usable as a key without
+/// [`build_assert_trusted`](crate::Builder::build_assert_trusted).
+///
+/// # Examples
+///
+/// ```
+/// flashmap::assert_trusted_hash_eq::<String>();
+///
+/// const _: () = flashmap::assert_trusted_hash_eq::<(u32, &str)>();
+/// ```
+///
+/// ```compile_fail
+/// flashmap::assert_trusted_hash_eq::<std::cell::Cell<u32>>();
+/// ```
+#[inline]
+pub const fn assert_trusted_hash_eq<T>()
+where
+    T: TrustedHashEq + ?Sized,
+{
+}
+
+/// Implements [`TrustedHashEq`](crate::TrustedHashEq) for each of the given types.
+///
+/// This is a shorthand for writing out `unsafe impl TrustedHashEq for Type {}` for each type,
+/// which is common for newtypes with derived `Hash` and `Eq` implementations. The list must be
+/// preceded by `unsafe`, since the caller is still responsible for upholding the safety contract
+/// of `TrustedHashEq` for every type listed. Generic types are preceded by their type parameters
+/// in braces, in which case every type parameter is required to implement `TrustedHashEq`.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(PartialEq, Eq, Hash)]
+/// struct Sha256([u8; 32]);
+///
+/// #[derive(PartialEq, Eq, Hash)]
+/// struct UserId(u64);
+///
+/// #[derive(PartialEq, Eq, Hash)]
+/// struct Tagged<T>(&'static str, T);
+///
+/// flashmap::trusted_hash_eq! {
+///     // Safety: the derived implementations only hash and compare types which implement
+///     // TrustedHashEq
+///     unsafe Sha256, UserId, {T} Tagged<T>
+/// }
+///
+/// let (mut write, read) = flashmap::new::<Tagged<UserId>, Sha256>();
+/// write.guard().insert(Tagged("user", UserId(1)), Sha256([0; 32]));
+/// assert!(read.guard().contains_key(&Tagged("user", UserId(1))));
+/// ```
+#[macro_export]
+macro_rules! trusted_hash_eq {
+    (unsafe $($({$($T:ident),+ $(,)?})? $Type:ty),+ $(,)?) => {
+        $(
+            unsafe impl$(<$($T),+>)? $crate::TrustedHashEq for $Type
+            $(where $($T: $crate::TrustedHashEq),+)? {}
+        )+
+    };
+}
+
 // This massive glut of impls was lifted from `evmap`:
 // https://github.com/jonhoo/evmap/blob/0daf488a76f9a2f271e0aab75e84cc65661df195/src/stable_hash_eq.rs
 
-macro_rules! trusted_hash_eq {
+macro_rules! impl_trusted_hash_eq {
     ($(
         $({$($a:lifetime),*$(,)?$($T:ident$(:?$Sized:ident)?),*$(,)?}
         $({$($manual_bounds:tt)*})?)? $Type:ty,
     )*) => {
-        trusted_hash_eq!{#
+        impl_trusted_hash_eq!{#
             $(
                 $({$($a)*$($T$(:?$Sized$Sized)?)*})? $($({where $($manual_bounds)*})?
                 {
@@ -74,7 +141,7 @@ use std::{
     time::{Duration, Instant, SystemTime},
 };
 
-trusted_hash_eq! {
+impl_trusted_hash_eq! {
     cmp::Ordering,
     Infallible,
     ErrorKind,
@@ -122,18 +189,6 @@ trusted_hash_eq! {
     {'a} Component<'a>,
     {'a} Prefix<'a>,
     {T} VecDeque<T>,
-    {A: ?Sized} (A,),
-    {A, B: ?Sized} (A, B),
-    {A, B, C: ?Sized} (A, B, C),
-    {A, B, C, D: ?Sized} (A, B, C, D),
-    {A, B, C, D, E: ?Sized} (A, B, C, D, E),
-    {A, B, C, D, E, F: ?Sized} (A, B, C, D, E, F),
-    {A, B, C, D, E, F, G: ?Sized} (A, B, C, D, E, F, G),
-    {A, B, C, D, E, F, G, H: ?Sized} (A, B, C, D, E, F, G, H),
-    {A, B, C, D, E, F, G, H, I: ?Sized} (A, B, C, D, E, F, G, H, I),
-    {A, B, C, D, E, F, G, H, I, J: ?Sized} (A, B, C, D, E, F, G, H, I, J),
-    {A, B, C, D, E, F, G, H, I, J, K: ?Sized} (A, B, C, D, E, F, G, H, I, J, K),
-    {A, B, C, D, E, F, G, H, I, J, K, L: ?Sized} (A, B, C, D, E, F, G, H, I, J, K, L),
     {Idx} Range<Idx>,
     {Idx} RangeFrom<Idx>,
     {Idx} RangeInclusive<Idx>,
@@ -144,7 +199,7 @@ trusted_hash_eq! {
 
 macro_rules! trusted_hash_eq_fn {
     ($({$($($A:ident),+)?})*) => {
-        trusted_hash_eq!{
+        impl_trusted_hash_eq!{
             $(
                 {Ret$(, $($A),+)?}{} fn($($($A),+)?) -> Ret,
                 {Ret$(, $($A),+)?}{} extern "C" fn($($($A),+)?) -> Ret,
@@ -173,7 +228,38 @@ trusted_hash_eq_fn! {
     {A, B, C, D, E, F, G, H, I, J, K, L}
 }
 
-trusted_hash_eq! {
+// The last element of a tuple may be unsized, in which case the standard library may not
+// implement `Hash` and `Eq` for the tuple, so those bounds are stated explicitly rather than
+// implied by the elements
+macro_rules! trusted_hash_eq_tuple {
+    ($({$($A:ident),*; $Last:ident})*) => {
+        $(
+            unsafe impl<$($A,)* $Last: ?Sized> TrustedHashEq for ($($A,)* $Last,)
+            where
+                $($A: TrustedHashEq,)*
+                $Last: TrustedHashEq,
+                Self: Hash + Eq,
+            {}
+        )*
+    };
+}
+
+trusted_hash_eq_tuple! {
+    {; A}
+    {A; B}
+    {A, B; C}
+    {A, B, C; D}
+    {A, B, C, D; E}
+    {A, B, C, D, E; F}
+    {A, B, C, D, E, F; G}
+    {A, B, C, D, E, F, G; H}
+    {A, B, C, D, E, F, G, H; I}
+    {A, B, C, D, E, F, G, H, I; J}
+    {A, B, C, D, E, F, G, H, I, J; K}
+    {A, B, C, D, E, F, G, H, I, J, K; L}
+}
+
+impl_trusted_hash_eq! {
     {T} Bound<T>,
     {T} Option<T>,
     {T} Poll<T>,
@@ -196,3 +282,21 @@ trusted_hash_eq! {
 }
 
 unsafe impl<T, const N: usize> TrustedHashEq for [T; N] where T: TrustedHashEq {}
+
+#[cfg(feature = "chrono")]
+impl_trusted_hash_eq! {
+    chrono::NaiveDate,
+    chrono::NaiveDateTime,
+    chrono::NaiveTime,
+    {Tz}{Tz: chrono::TimeZone} chrono::DateTime<Tz>,
+}
+
+#[cfg(feature = "smol_str")]
+impl_trusted_hash_eq! {
+    smol_str::SmolStr,
+}
+
+#[cfg(feature = "uuid")]
+impl_trusted_hash_eq! {
+    uuid::Uuid,
+}
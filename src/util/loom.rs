@@ -1,8 +1,35 @@
 #[cfg(loom)]
-pub use loom::{hint, thread};
+#[allow(unused_imports)]
+pub use loom::hint;
+
+#[cfg(loom)]
+pub mod thread {
+    pub use loom::thread::*;
+
+    // Loom cannot model time, so a timed park is treated as a spurious wakeup
+    pub fn park_timeout(_dur: std::time::Duration) {
+        yield_now();
+    }
+}
+
+#[cfg(not(loom))]
+#[allow(unused_imports)]
+pub use std::hint;
 
 #[cfg(not(loom))]
-pub use std::{hint, sync, thread};
+pub use std::thread;
+
+#[cfg(all(not(loom), not(feature = "portable-atomic")))]
+pub use std::sync;
+
+#[cfg(all(not(loom), feature = "portable-atomic"))]
+pub mod sync {
+    pub use std::sync::*;
+
+    pub mod atomic {
+        pub use portable_atomic::{fence, AtomicIsize, AtomicUsize, Ordering};
+    }
+}
 
 #[cfg(loom)]
 pub mod sync {
@@ -2,9 +2,11 @@ mod aliasing;
 mod cache_padded;
 pub mod deterministic;
 pub mod loom;
+mod seeded;
 
 pub use aliasing::*;
 pub use cache_padded::*;
+pub use seeded::*;
 
 use self::loom::sync::{Mutex, MutexGuard, PoisonError};
 
@@ -0,0 +1,60 @@
+// `SipHasher` is deprecated only in favour of `DefaultHasher`, whose algorithm is unspecified, so
+// it remains the one std hasher that can be keyed with a stable seed.
+#[allow(deprecated)]
+use std::hash::SipHasher;
+use std::hash::{BuildHasher, Hasher};
+
+/// A deterministic [`BuildHasher`](std::hash::BuildHasher) which produces SipHash-2-4 hashers
+/// keyed by a fixed seed.
+///
+/// Unlike [`RandomState`](std::collections::hash_map::RandomState), two `SeededState`s created
+/// from the same seed will always produce the same hashes, which makes the iteration order of the
+/// map reproducible across runs. Note that this also means the map is not resistant to HashDoS
+/// attacks if the seed is known.
+///
+/// See [`Builder::with_seed`](crate::Builder::with_seed).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeededState {
+    seed: u64,
+}
+
+impl SeededState {
+    /// Creates a new `SeededState` from the given seed.
+    #[inline]
+    pub const fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Returns the seed used by this hasher builder.
+    #[inline]
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl BuildHasher for SeededState {
+    type Hasher = SeededHasher;
+
+    #[inline]
+    #[allow(deprecated)]
+    fn build_hasher(&self) -> Self::Hasher {
+        SeededHasher(SipHasher::new_with_keys(self.seed, self.seed))
+    }
+}
+
+/// The hasher created by [`SeededState`](crate::SeededState).
+#[derive(Clone, Debug)]
+#[allow(deprecated)]
+pub struct SeededHasher(SipHasher);
+
+impl Hasher for SeededHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.write(bytes);
+    }
+}
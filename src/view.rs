@@ -1,9 +1,14 @@
 use std::borrow::Borrow;
-use std::hash::{BuildHasher, Hash};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, Hasher};
 use std::ops::Deref;
 
-use crate::util::BorrowHelper;
-use crate::{Evicted, Leaked, Map, WriteGuard};
+use crate::util::{Alias, BorrowHelper};
+use crate::{
+    EntryRef, Evicted, Leaked, Map, MapOp, OwnedReadGuard, RawEntryBuilderMut, ReadGuard, ValueMut,
+    VersionMismatch, WriteGuard,
+};
 
 pub(crate) mod sealed {
     pub trait ReadAccess {
@@ -84,6 +89,21 @@ where
         self.guard.with_map(Map::len)
     }
 
+    /// Returns the number of entries the copy of the map viewed through this guard can hold
+    /// without reallocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (write, read) = flashmap::with_capacity::<u32, u32>(100);
+    /// assert!(read.guard().capacity() >= 100);
+    /// ```
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.guard.with_map(Map::capacity)
+    }
+
     /// Returns whether or not the map contains the given key.
     ///
     /// # Examples
@@ -99,10 +119,10 @@ where
     /// assert!(!guard.contains_key(&1));
     /// ```
     #[inline]
-    pub fn contains_key<Q: ?Sized>(&self, key: &Q) -> bool
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
     where
         K: Borrow<Q> + Eq + Hash,
-        Q: Hash + Eq,
+        Q: Hash + Eq + ?Sized,
     {
         self.guard
             .with_map(|map| map.contains_key(BorrowHelper::new_ref(key)))
@@ -123,15 +143,175 @@ where
     /// assert!(guard.get("bananas").is_none());
     /// ```
     #[inline]
-    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&V>
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
     where
         K: Borrow<Q> + Eq + Hash,
-        Q: Hash + Eq,
+        Q: Hash + Eq + ?Sized,
     {
         self.guard
             .with_map(|map| map.get(BorrowHelper::new_ref(key)).map(Deref::deref))
     }
 
+    /// Returns a clone of the value corresponding to the key.
+    ///
+    /// This is useful when values are cheap to clone, such as when they are wrapped in an
+    /// [`Arc`](std::sync::Arc), since the guard can be dropped immediately afterwards rather than
+    /// being held onto, which would block the writer from publishing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use std::sync::Arc;
+    ///
+    /// let (mut write, read) = flashmap::new::<&'static str, Arc<String>>();
+    ///
+    /// write.guard().insert("config", Arc::new("verbose".to_owned()));
+    ///
+    /// let config = read.guard().get_cloned("config").unwrap();
+    /// write.guard().remove("config");
+    /// assert_eq!(*config, "verbose");
+    /// ```
+    #[inline]
+    pub fn get_cloned<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q> + Eq + Hash,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.get(key).cloned()
+    }
+
+    /// Returns a reference to the value corresponding to the key, or the error produced by the
+    /// given function if the key is not present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// #[derive(Debug, PartialEq)]
+    /// struct NotFound(&'static str);
+    ///
+    /// let (mut write, read) = flashmap::new::<&'static str, u32>();
+    /// write.guard().insert("width", 640);
+    ///
+    /// let area = || -> Result<u32, NotFound> {
+    ///     let guard = read.guard();
+    ///     let width = guard.get_or_err("width", || NotFound("width"))?;
+    ///     let height = guard.get_or_err("height", || NotFound("height"))?;
+    ///     Ok(width * height)
+    /// };
+    ///
+    /// assert_eq!(area(), Err(NotFound("height")));
+    /// ```
+    #[inline]
+    pub fn get_or_err<Q, E, F>(&self, key: &Q, err: F) -> Result<&V, E>
+    where
+        K: Borrow<Q> + Eq + Hash,
+        Q: Hash + Eq + ?Sized,
+        F: FnOnce() -> E,
+    {
+        self.get(key).ok_or_else(err)
+    }
+
+    /// Returns references to the values corresponding to each of the given keys.
+    ///
+    /// All values are read from the same snapshot of the map. Since a view only ever gives out
+    /// shared references, any number of references obtained through [`get`](crate::View::get)
+    /// may coexist as well; this method is just a convenience for looking up several keys at
+    /// once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
+    /// let mut guard = write.guard();
+    /// guard.insert(1, 10);
+    /// guard.insert(2, 20);
+    /// guard.publish();
+    ///
+    /// let guard = read.guard();
+    /// assert_eq!(guard.get_many([&1, &2, &3]), [Some(&10), Some(&20), None]);
+    /// ```
+    #[inline]
+    pub fn get_many<Q, const N: usize>(&self, keys: [&Q; N]) -> [Option<&V>; N]
+    where
+        K: Borrow<Q> + Eq + Hash,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.guard
+            .with_map(|map| keys.map(|key| map.get(BorrowHelper::new_ref(key)).map(Deref::deref)))
+    }
+
+    /// Returns references to the values corresponding to two keys. This is equivalent to
+    /// `get_many([a, b])`. See [`get_many`](crate::View::get_many) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<String, u32>();
+    ///
+    /// write.guard().insert("left".to_owned(), 1);
+    ///
+    /// let guard = read.guard();
+    /// assert_eq!(guard.get_pair("left", "right"), (Some(&1), None));
+    /// ```
+    #[inline]
+    pub fn get_pair<Q>(&self, a: &Q, b: &Q) -> (Option<&V>, Option<&V>)
+    where
+        K: Borrow<Q> + Eq + Hash,
+        Q: Hash + Eq + ?Sized,
+    {
+        let [a, b] = self.get_many([a, b]);
+        (a, b)
+    }
+
+    /// Looks up the given key, returning the entry along with the hash that was used to find it.
+    ///
+    /// The hash is computed with the map's hasher, so it can be reused by other data structures
+    /// built with an equivalent hasher without hashing the key again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use std::hash::BuildHasher;
+    ///
+    /// let (mut write, read) = flashmap::Builder::new().with_seed(1).build::<String, u32>();
+    /// write.guard().insert("ferris".to_owned(), 7);
+    ///
+    /// let guard = read.guard();
+    /// let found = guard.lookup("ferris").unwrap();
+    /// assert_eq!(*found, 7);
+    /// assert_eq!(found.key(), "ferris");
+    ///
+    /// let hasher = flashmap::SeededState::new(1);
+    /// assert_eq!(found.hash(), hasher.hash_one("ferris"));
+    ///
+    /// assert!(guard.lookup("crab").is_none());
+    /// ```
+    #[inline]
+    pub fn lookup<Q>(&self, key: &Q) -> Option<Found<'_, K, V>>
+    where
+        K: Borrow<Q> + Eq + Hash,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.guard.with_map(|map| {
+            let hash = map.hasher().hash_one(key);
+
+            map.raw_entry()
+                .from_key_hashed_nocheck(hash, BorrowHelper::new_ref(key))
+                .map(|(key, value)| Found {
+                    key: key.deref(),
+                    value: value.deref(),
+                    hash,
+                })
+        })
+    }
+
     /// An iterator visiting all key-value pairs in arbitrary order.
     ///
     /// # Examples
@@ -155,7 +335,7 @@ where
     /// assert_eq!(result, i8::MAX);
     /// ```
     #[inline]
-    pub fn iter<'read>(&'read self) -> impl Iterator<Item = (&K, &V)> + '_
+    pub fn iter<'read>(&'read self) -> impl Iterator<Item = (&'read K, &'read V)> + 'read
     where
         (K, V): 'read,
     {
@@ -186,7 +366,7 @@ where
     /// assert_eq!(result, 111);
     /// ```
     #[inline]
-    pub fn keys<'read>(&'read self) -> impl Iterator<Item = &K> + '_
+    pub fn keys<'read>(&'read self) -> impl Iterator<Item = &'read K> + 'read
     where
         (K, V): 'read,
     {
@@ -216,156 +396,1673 @@ where
     /// assert_eq!(result, 111);
     /// ```
     #[inline]
-    pub fn values<'read>(&'read self) -> impl Iterator<Item = &V> + '_
+    pub fn values<'read>(&'read self) -> impl Iterator<Item = &'read V> + 'read
     where
         (K, V): 'read,
     {
         self.guard.with_map(|map| map.values().map(Deref::deref))
     }
-}
 
-// TODO: It would probably be nicer if the write functionality got abstracted out into traits, but
-// that is a massive headache I don't want to deal with, so we're doing this for now.
-impl<'guard, K, V, S> View<WriteGuard<'guard, K, V, S>>
-where
-    K: Eq + Hash,
-    S: BuildHasher,
-{
-    /// Inserts a key-value pair into the map.
+    /// Clones every key in the map into a new vector, in arbitrary order.
     ///
-    /// If the map did not have this key present, then `None` is returned. If it did, then the
-    /// evicted value is returned. See [`Evicted`](crate::Evicted) for details.
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
+    /// let mut guard = write.guard();
+    /// guard.insert(1, 10);
+    /// guard.insert(2, 20);
+    /// guard.publish();
+    ///
+    /// let mut keys = read.guard().keys_vec();
+    /// keys.sort_unstable();
+    /// assert_eq!(keys, [1, 2]);
+    /// ```
+    #[inline]
+    pub fn keys_vec(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
+        self.guard.with_map(|map| {
+            let mut keys = Vec::with_capacity(map.len());
+            keys.extend(map.keys().map(|key| K::clone(key)));
+            keys
+        })
+    }
+
+    /// Clones every value in the map into a new vector, in arbitrary order.
     ///
     /// # Examples
     ///
     /// ```
     /// # use flashmap;
-    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
     /// let mut guard = write.guard();
+    /// guard.insert(1, 10);
+    /// guard.insert(2, 20);
+    /// guard.publish();
     ///
-    /// assert!(guard.insert(17, "seven teen".to_owned()).is_none());
-    /// assert_eq!(&*guard.insert(17, "seventeen".to_owned()).unwrap(), "seven teen");
+    /// let mut values = read.guard().values_vec();
+    /// values.sort_unstable();
+    /// assert_eq!(values, [10, 20]);
     /// ```
     #[inline]
-    pub fn insert<'ret>(&mut self, key: K, value: V) -> Option<Evicted<'ret, K, V>>
+    pub fn values_vec(&self) -> Vec<V>
     where
-        'guard: 'ret,
+        V: Clone,
     {
-        self.guard.insert(key, value)
+        self.guard.with_map(|map| {
+            let mut values = Vec::with_capacity(map.len());
+            values.extend(map.values().map(|value| V::clone(value)));
+            values
+        })
     }
 
-    /// Replaces the value associated with the given key according to the provided function.
+    /// Clones every key-value pair in the map into a new vector, in arbitrary order.
     ///
-    /// If the key is not present, then the function is not called, and `None` is returned. If the
-    /// key is present, then the function is called with the current value provided as the argument,
-    /// the value in the map is replaced, and the evicted value is returned. See
-    /// [`Evicted`](crate::Evicted) for details.
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
+    /// let mut guard = write.guard();
+    /// guard.insert(1, 10);
+    /// guard.insert(2, 20);
+    /// guard.publish();
+    ///
+    /// let mut entries = read.guard().entries_vec();
+    /// entries.sort_unstable();
+    /// assert_eq!(entries, [(1, 10), (2, 20)]);
+    /// ```
+    #[inline]
+    pub fn entries_vec(&self) -> Vec<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.guard.with_map(|map| {
+            let mut entries = Vec::with_capacity(map.len());
+            entries.extend(
+                map.iter()
+                    .map(|(key, value)| (K::clone(key), V::clone(value))),
+            );
+            entries
+        })
+    }
+
+    /// Clones every key-value pair in the map into any collection which can be built from an
+    /// iterator of pairs, such as a `BTreeMap` or a `Vec<(K, V)>`.
     ///
     /// # Examples
     ///
     /// ```
     /// # use flashmap;
-    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// use std::collections::BTreeMap;
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, &str>();
+    ///
     /// let mut guard = write.guard();
+    /// guard.insert(2, "b");
+    /// guard.insert(1, "a");
+    /// guard.publish();
     ///
-    /// guard.insert(1, "a".to_owned());
+    /// let sorted = read.guard().collect_into::<BTreeMap<_, _>>();
+    /// assert!(sorted.into_iter().eq([(1, "a"), (2, "b")]));
+    /// ```
+    #[inline]
+    pub fn collect_into<B>(&self) -> B
+    where
+        B: FromIterator<(K, V)>,
+        K: Clone,
+        V: Clone,
+    {
+        self.iter()
+            .map(|(key, value)| (K::clone(key), V::clone(value)))
+            .collect()
+    }
+
+    /// Folds every key-value pair in the map into an accumulator, visiting entries in arbitrary
+    /// order.
     ///
-    /// // The key 0 is not in the map, so nothing changes
-    /// assert!(guard.replace(0, |_| String::new()).is_none());
-    /// assert_eq!(guard.get(&1).unwrap(), "a");
+    /// # Examples
     ///
-    /// // The key 1 is in the map, so the closure gets called
-    /// let evicted = guard.replace(1, |old| {
-    ///     assert_eq!(old, "a");
-    ///     "b".to_owned()
-    /// }).unwrap();
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
     ///
-    /// // We evicted the old value "a"
-    /// assert_eq!(&*evicted, "a");
+    /// let mut guard = write.guard();
+    /// guard.insert(1, 2);
+    /// guard.insert(3, 4);
+    /// guard.publish();
     ///
-    /// // And now "b" is in the map
-    /// assert_eq!(guard.get(&1).unwrap(), "b");
+    /// let sum = read.guard().fold(0, |acc, (&key, &value)| acc + key * value);
+    /// assert_eq!(sum, 14);
     /// ```
     #[inline]
-    pub fn replace<'ret, F>(&mut self, key: K, op: F) -> Option<Evicted<'ret, K, V>>
+    pub fn fold<B, F>(&self, init: B, mut op: F) -> B
     where
-        F: FnOnce(&V) -> V,
-        'guard: 'ret,
+        F: FnMut(B, (&K, &V)) -> B,
     {
-        self.guard.replace(key, op)
+        self.guard.with_map(|map| {
+            map.iter()
+                .fold(init, |acc, (key, value)| op(acc, (key, value)))
+        })
     }
 
-    /// Removes a key from the map, returning the value at the key if the key was previously in the
-    /// map. See [`Evicted`](crate::Evicted) for details on accessing the removed value.
+    /// Calls the given function on every key-value pair in the map in arbitrary order.
     ///
     /// # Examples
     ///
     /// ```
     /// # use flashmap;
     /// let (mut write, read) = flashmap::new::<u32, String>();
-    /// let mut guard = write.guard();
     ///
-    /// guard.insert(0, "a".to_owned());
+    /// let mut guard = write.guard();
+    /// guard.insert(1, "one".to_owned());
+    /// guard.insert(2, "two".to_owned());
+    /// guard.publish();
     ///
-    /// assert_eq!(&*guard.remove(0).unwrap(), "a");
-    /// assert!(guard.remove(0).is_none());
-    /// assert!(guard.remove(1).is_none());
+    /// let mut total_len = 0;
+    /// read.guard().for_each(|_, value| total_len += value.len());
+    /// assert_eq!(total_len, 6);
     /// ```
     #[inline]
-    pub fn remove<'ret>(&mut self, key: K) -> Option<Evicted<'ret, K, V>>
+    pub fn for_each<F>(&self, mut op: F)
     where
-        'guard: 'ret,
+        F: FnMut(&K, &V),
     {
-        self.guard.remove(key)
+        self.guard.with_map(|map| {
+            map.iter().for_each(|(key, value)| op(key, value));
+        })
     }
 
-    /// Takes ownership of a leaked value and drops the inner value when it is safe to do so.
+    /// Computes a hash of the contents of the map which does not depend on iteration order.
     ///
-    /// There are no guarantees regarding when the leaked value will be dropped. It is only
-    /// guaranteed that it will eventually be dropped provided that no handles or guards
-    /// associated with this map are leaked or forgotten.
+    /// Two views with equal contents always produce the same hash, even if they belong to
+    /// different maps with different hashers, so this can be used to cheaply check whether
+    /// anything changed between two snapshots without cloning either of them. This is the same
+    /// hash fed to a [`Hasher`](std::hash::Hasher) by the [`Hash`](std::hash::Hash)
+    /// implementation of `View`. The exact value is not guaranteed to be stable across Rust
+    /// releases, so it should not be persisted.
     ///
-    /// # Panics
+    /// # Examples
     ///
-    /// Panics if the provided leaked value came from a different map then the one this guard is
-    /// associated with. Note that it is **not** required that this method is called with the same
-    /// guard that created the leaked value.
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
+    /// let mut guard = write.guard();
+    /// guard.insert(1, 2);
+    /// guard.insert(3, 4);
+    /// guard.publish();
+    /// let before = read.guard().content_hash();
+    ///
+    /// // Removing and reinserting an entry does not change the contents
+    /// let mut guard = write.guard();
+    /// guard.remove(1);
+    /// guard.insert(1, 2);
+    /// guard.publish();
+    /// assert_eq!(read.guard().content_hash(), before);
+    ///
+    /// write.guard().insert(3, 5);
+    /// assert_ne!(read.guard().content_hash(), before);
+    /// ```
+    pub fn content_hash(&self) -> u64
+    where
+        K: Hash,
+        V: Hash,
+    {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Computes the differences between this view and another view, treating this view as the
+    /// old version of the map and `other` as the new version.
+    ///
+    /// The returned iterator yields a [`DiffEntry`](crate::DiffEntry) for every key which was
+    /// added, removed, or whose value changed, in arbitrary order. The two views may belong to
+    /// different maps, or to different versions of the same map.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use flashmap::{self, Evicted};
-    /// let (mut write, read) = flashmap::new::<String, String>();
+    /// # use flashmap;
+    /// use flashmap::DiffEntry;
     ///
-    /// write.guard().insert("ferris".to_owned(), "crab".to_owned());
+    /// let (mut write, read) = flashmap::new::<u32, &str>();
     ///
-    /// // ~~ stuff happens ~~
+    /// let mut guard = write.guard();
+    /// guard.insert(1, "a");
+    /// guard.insert(2, "b");
+    /// guard.publish();
+    ///
+    /// let old = read.guard_owned();
     ///
     /// let mut guard = write.guard();
+    /// guard.remove(1);
+    /// guard.insert(2, "c");
+    /// guard.insert(3, "d");
+    /// guard.publish();
     ///
-    /// // Remove the value and leak it
-    /// let leaked = guard.remove("ferris".to_owned())
-    ///     .map(Evicted::leak)
-    ///     .unwrap();
-    /// assert_eq!(&*leaked, "crab");
+    /// let new = read.guard_owned();
     ///
-    /// // We decide we don't need to keep the leaked value around, so we tell the guard
-    /// // to drop it when it is safe to do so
-    /// guard.drop_lazily(leaked);
+    /// let mut diff = old.diff(&new).collect::<Vec<_>>();
+    /// diff.sort_by_key(|entry| *entry.key());
     ///
-    /// guard.publish();
+    /// assert_eq!(diff, [
+    ///     DiffEntry::Removed(&1, &"a"),
+    ///     DiffEntry::Changed(&2, &"b", &"c"),
+    ///     DiffEntry::Added(&3, &"d"),
+    /// ]);
     /// ```
-    #[inline]
-    pub fn drop_lazily(&self, leaked: Leaked<V>) {
-        self.guard.drop_lazily(leaked)
+    pub fn diff<'a, G2, S2>(&'a self, other: &'a View<G2>) -> Diff<'a, K, V>
+    where
+        G2: sealed::ReadAccess<Map = Map<K, V, S2>>,
+        K: Eq + Hash,
+        V: PartialEq,
+        S2: BuildHasher,
+    {
+        let removed_or_changed = self
+            .iter()
+            .filter_map(move |(key, old)| match other.get(key) {
+                None => Some(DiffEntry::Removed(key, old)),
+                Some(new) if old != new => Some(DiffEntry::Changed(key, old, new)),
+                Some(_) => None,
+            });
+        let added = other
+            .iter()
+            .filter(move |(key, _)| !self.contains_key(*key))
+            .map(|(key, new)| DiffEntry::Added(key, new));
+
+        Diff {
+            inner: Box::new(removed_or_changed.chain(added)),
+        }
     }
 
-    /// Consumes this view and its guard, publishing all previous changes to the map.
+    /// Calls the given function with a reference to the underlying
+    /// [`hashbrown`](https://crates.io/crates/hashbrown) map.
     ///
-    /// This has the same effect as dropping the view. Note that the changes will only be visible
-    /// through newly created read or write guards.
-    #[inline]
-    pub fn publish(self) {
-        self.guard.publish()
+    /// This is an escape hatch for read-only operations which are not exposed by this type. Keys
+    /// and values are stored as [`Alias`](crate::Alias)es, which dereference to the underlying
+    /// key or value. Note that the map cannot be indexed with a plain `&K` due to how keys are
+    /// stored, so lookups should be performed with [`get`](crate::View::get) or via the
+    /// hash-based raw entry API.
+    ///
+    /// The exact type of the map is an implementation detail, and may change between minor
+    /// versions of this crate if `hashbrown` is upgraded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use std::hash::BuildHasher;
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    /// write.guard().insert(1, 2);
+    ///
+    /// let guard = read.guard();
+    /// let capacity = guard.with_raw_map(|map| map.capacity());
+    /// assert!(capacity >= 1);
+    ///
+    /// let value = guard.with_raw_map(|map| {
+    ///     let hash = map.hasher().hash_one(1u32);
+    ///     map.raw_entry().from_hash(hash, |key| **key == 1).map(|(_, value)| **value)
+    /// });
+    /// assert_eq!(value, Some(2));
+    /// ```
+    #[inline]
+    pub fn with_raw_map<'read, F, R>(&'read self, op: F) -> R
+    where
+        F: FnOnce(&'read hashbrown::HashMap<Alias<K>, Alias<V>, S>) -> R,
+        K: 'read,
+        V: 'read,
+        S: 'read,
+    {
+        self.guard.with_map(op)
+    }
+}
+
+impl<K, V, S, G> View<G>
+where
+    G: sealed::ReadAccess<Map = Map<K, Option<V>, S>>,
+    S: BuildHasher,
+{
+    /// Returns a reference to the value corresponding to the key in a map of optional values,
+    /// flattening the two layers of `Option`.
+    ///
+    /// This returns `None` both if the key is not present, and if it is mapped to `None`. Use
+    /// [`get`](crate::View::get) to tell those cases apart.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<&'static str, Option<u32>>();
+    ///
+    /// let mut guard = write.guard();
+    /// guard.insert("found", Some(1));
+    /// guard.insert("known missing", None);
+    /// guard.publish();
+    ///
+    /// let guard = read.guard();
+    /// assert_eq!(guard.get_flatten("found"), Some(&1));
+    /// assert_eq!(guard.get_flatten("known missing"), None);
+    /// assert_eq!(guard.get_flatten("unknown"), None);
+    /// assert_eq!(guard.get("known missing"), Some(&None));
+    /// ```
+    #[inline]
+    pub fn get_flatten<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q> + Eq + Hash,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).and_then(Option::as_ref)
+    }
+}
+
+/// A single difference between two views of a map. See [`View::diff`](crate::View::diff).
+#[derive(Debug, PartialEq, Eq)]
+pub enum DiffEntry<'a, K, V> {
+    /// The key is only present in the new view.
+    Added(&'a K, &'a V),
+    /// The key is only present in the old view.
+    Removed(&'a K, &'a V),
+    /// The key is present in both views, with the old and new values respectively.
+    Changed(&'a K, &'a V, &'a V),
+}
+
+impl<'a, K, V> DiffEntry<'a, K, V> {
+    /// Returns the key which this entry describes.
+    #[inline]
+    pub fn key(&self) -> &'a K {
+        match *self {
+            Self::Added(key, _) | Self::Removed(key, _) | Self::Changed(key, _, _) => key,
+        }
+    }
+}
+
+impl<K, V> Clone for DiffEntry<'_, K, V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for DiffEntry<'_, K, V> {}
+
+/// An iterator over the differences between two views of a map. See
+/// [`View::diff`](crate::View::diff).
+pub struct Diff<'a, K, V> {
+    inner: Box<dyn Iterator<Item = DiffEntry<'a, K, V>> + 'a>,
+}
+
+impl<'a, K, V> Iterator for Diff<'a, K, V> {
+    type Item = DiffEntry<'a, K, V>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+/// Compares the contents of the map as seen by this view to a standard library `HashMap`.
+///
+/// # Examples
+///
+/// ```
+/// # use flashmap;
+/// use std::collections::HashMap;
+///
+/// let (mut write, read) = flashmap::new::<u32, String>();
+/// let mut oracle = HashMap::new();
+///
+/// write.guard().insert(1, "one".to_owned());
+/// oracle.insert(1, "one".to_owned());
+///
+/// assert!(read.guard() == oracle);
+/// assert!(oracle == read.guard());
+/// ```
+impl<K, V, S, G, S2> PartialEq<HashMap<K, V, S2>> for View<G>
+where
+    G: sealed::ReadAccess<Map = Map<K, V, S>>,
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+    S2: BuildHasher,
+{
+    fn eq(&self, other: &HashMap<K, V, S2>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K, V, S, G, S2> PartialEq<View<G>> for HashMap<K, V, S2>
+where
+    G: sealed::ReadAccess<Map = Map<K, V, S>>,
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+    S2: BuildHasher,
+{
+    #[inline]
+    fn eq(&self, other: &View<G>) -> bool {
+        other == self
+    }
+}
+
+/// A minimal read-only map interface.
+///
+/// This trait allows views of a `flashmap` to be passed to code written against a generic map
+/// interface, including as a trait object, without that code needing to know about guards. It is
+/// also implemented for the standard library's [`HashMap`](std::collections::HashMap).
+///
+/// # Examples
+///
+/// ```
+/// # use flashmap;
+/// use flashmap::MapLike;
+/// use std::collections::HashMap;
+///
+/// fn total(map: &dyn MapLike<&'static str, u32>) -> u32 {
+///     ["a", "b", "c"].iter().filter_map(|key| map.get(key)).sum()
+/// }
+///
+/// let (mut write, read) = flashmap::new::<&'static str, u32>();
+/// let mut guard = write.guard();
+/// guard.insert("a", 1);
+/// guard.insert("b", 2);
+/// guard.publish();
+///
+/// let std_map = HashMap::from([("a", 1), ("b", 2)]);
+///
+/// assert_eq!(total(&read.guard()), 3);
+/// assert_eq!(total(&std_map), 3);
+/// ```
+pub trait MapLike<K, V> {
+    /// Returns a reference to the value corresponding to the key.
+    fn get(&self, key: &K) -> Option<&V>;
+
+    /// Returns the number of elements in the map.
+    fn len(&self) -> usize;
+
+    /// Returns whether or not the map contains the given key.
+    #[inline]
+    fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns whether or not the map is empty.
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K, V, S, G> MapLike<K, V> for View<G>
+where
+    G: sealed::ReadAccess<Map = Map<K, V, S>>,
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    #[inline]
+    fn get(&self, key: &K) -> Option<&V> {
+        View::get(self, key)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        View::len(self)
+    }
+}
+
+impl<K, V, S> MapLike<K, V> for HashMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    #[inline]
+    fn get(&self, key: &K) -> Option<&V> {
+        HashMap::get(self, key)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+}
+
+/// An entry found via [`View::lookup`](crate::View::lookup).
+///
+/// This type dereferences to the value of the entry, and additionally exposes the key and the
+/// hash which was used to find the entry.
+pub struct Found<'read, K, V> {
+    key: &'read K,
+    value: &'read V,
+    hash: u64,
+}
+
+impl<'read, K, V> Found<'read, K, V> {
+    /// Returns the key of the entry.
+    #[inline]
+    pub fn key(&self) -> &'read K {
+        self.key
+    }
+
+    /// Returns the value of the entry. Unlike dereferencing, the returned reference is bound to
+    /// the guard rather than to this `Found`.
+    #[inline]
+    pub fn value(&self) -> &'read V {
+        self.value
+    }
+
+    /// Returns the hash of the key as computed by the map's hasher.
+    #[inline]
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl<K, V> Clone for Found<'_, K, V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for Found<'_, K, V> {}
+
+impl<K, V> Deref for Found<'_, K, V> {
+    type Target = V;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+/// Compares the contents of two maps, which may be different maps or different versions of the
+/// same map.
+///
+/// # Examples
+///
+/// ```
+/// # use flashmap;
+/// let (mut write, read) = flashmap::new::<u32, u32>();
+///
+/// let old = read.guard_owned();
+/// write.guard().insert(1, 2);
+/// let new = read.guard_owned();
+///
+/// assert!(old != new);
+/// assert!(new == read.guard());
+/// ```
+impl<K, V, S, G, S2, G2> PartialEq<View<G2>> for View<G>
+where
+    G: sealed::ReadAccess<Map = Map<K, V, S>>,
+    G2: sealed::ReadAccess<Map = Map<K, V, S2>>,
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+    S2: BuildHasher,
+{
+    fn eq(&self, other: &View<G2>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K, V, S, G> Eq for View<G>
+where
+    G: sealed::ReadAccess<Map = Map<K, V, S>>,
+    K: Eq + Hash,
+    V: Eq,
+    S: BuildHasher,
+{
+}
+
+/// Hashes the contents of the map independently of iteration order, so that equal views always
+/// produce equal hashes, even if they use different hashers.
+///
+/// # Examples
+///
+/// ```
+/// # use flashmap;
+/// use std::collections::HashSet;
+///
+/// let (mut write, read) = flashmap::new::<u32, u32>();
+/// write.guard().insert(1, 2);
+///
+/// let mut snapshots = HashSet::new();
+/// snapshots.insert(read.guard_owned());
+/// snapshots.insert(read.guard_owned());
+/// assert_eq!(snapshots.len(), 1);
+/// ```
+impl<K, V, S, G> Hash for View<G>
+where
+    G: sealed::ReadAccess<Map = Map<K, V, S>>,
+    K: Hash,
+    V: Hash,
+    S: BuildHasher,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Each entry is hashed with a fixed-key hasher and the results are combined with XOR,
+        // which is commutative, so the result does not depend on iteration order.
+        let combined = self.fold(0u64, |combined, entry| {
+            let mut hasher = DefaultHasher::new();
+            entry.hash(&mut hasher);
+            combined ^ hasher.finish()
+        });
+
+        state.write_usize(self.len());
+        state.write_u64(combined);
+    }
+}
+
+impl<'guard, K, V, S> View<ReadGuard<'guard, K, V, S>> {
+    /// Returns whether or not the writer has published changes since this guard was created.
+    ///
+    /// A read guard always sees the version of the map which was published when it was created.
+    /// If this returns `true`, then a newer version is available, and creating a new guard will
+    /// observe it. Note that the writer may publish at any time, so a return value of `false` is
+    /// only a snapshot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
+    /// let guard = read.guard();
+    /// assert!(!guard.is_stale());
+    ///
+    /// write.guard().insert(1, 2);
+    /// assert!(guard.is_stale());
+    /// assert!(guard.get(&1).is_none());
+    ///
+    /// drop(guard);
+    /// let guard = read.guard();
+    /// assert!(!guard.is_stale());
+    /// assert_eq!(*guard.get(&1).unwrap(), 2);
+    /// ```
+    #[inline]
+    pub fn is_stale(&self) -> bool {
+        self.guard.is_stale()
+    }
+
+    #[inline]
+    pub(crate) fn snapshot_version(&self) -> u64 {
+        self.guard.snapshot_version()
+    }
+
+    /// Returns an iterator over the entries of the map in the order in which their keys were
+    /// inserted. This requires the map to have been built with
+    /// [`Builder::ordered`](crate::Builder::ordered).
+    ///
+    /// Replacing the value of an existing key does not change its position, whereas removing a
+    /// key and inserting it again moves it to the end.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map was not built with [`Builder::ordered`](crate::Builder::ordered).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::Builder;
+    ///
+    /// let (mut write, read) = Builder::new().ordered().build::<String, u32>();
+    ///
+    /// let mut guard = write.guard();
+    /// guard.insert("b".to_owned(), 1);
+    /// guard.insert("a".to_owned(), 2);
+    /// guard.insert("c".to_owned(), 3);
+    /// guard.remove("b".to_owned());
+    /// guard.insert("b".to_owned(), 4);
+    /// guard.insert("a".to_owned(), 5);
+    /// guard.publish();
+    ///
+    /// let guard = read.guard();
+    /// let entries = guard
+    ///     .iter_ordered()
+    ///     .map(|(key, &value)| (key.as_str(), value))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(entries, [("a", 5), ("c", 3), ("b", 4)]);
+    /// ```
+    #[inline]
+    pub fn iter_ordered(&self) -> impl Iterator<Item = (&K, &V)>
+    where
+        K: Hash + Eq,
+        S: BuildHasher,
+    {
+        self.guard.iter_ordered()
+    }
+
+    /// Returns a reference to the value corresponding to the key along with the entry's version.
+    /// This requires the map to have been built with
+    /// [`Builder::versioned`](crate::Builder::versioned).
+    ///
+    /// The version can later be passed to
+    /// [`replace_if_version`](crate::View::replace_if_version), which only replaces the value if
+    /// the entry was not changed in the meantime. This allows readers to prepare changes which the
+    /// writer applies optimistically.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map was not built with [`Builder::versioned`](crate::Builder::versioned).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::Builder;
+    ///
+    /// let (mut write, read) = Builder::new().versioned().build::<u32, u32>();
+    ///
+    /// write.guard().insert(1, 10);
+    /// let (_, first) = read.guard().get_versioned(&1).map(|(&v, ver)| (v, ver)).unwrap();
+    ///
+    /// write.guard().insert(1, 10);
+    /// let (_, second) = read.guard().get_versioned(&1).map(|(&v, ver)| (v, ver)).unwrap();
+    ///
+    /// // Every change produces a new version, even if the value is the same
+    /// assert_ne!(first, second);
+    /// assert!(read.guard().get_versioned(&2).is_none());
+    /// ```
+    #[inline]
+    pub fn get_versioned<Q>(&self, key: &Q) -> Option<(&V, u64)>
+    where
+        K: Hash + Eq + Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        S: BuildHasher,
+    {
+        self.guard.get_versioned(key)
+    }
+}
+
+impl<K, V, S> View<OwnedReadGuard<K, V, S>> {
+    /// Returns whether or not the writer has published changes since this guard was created.
+    ///
+    /// See [`View::<ReadGuard>::is_stale`](crate::View::is_stale) for details.
+    #[inline]
+    pub fn is_stale(&self) -> bool {
+        self.guard.is_stale()
+    }
+
+    /// Returns an iterator over the entries of the map in the order in which their keys were
+    /// inserted.
+    ///
+    /// See [`View::<ReadGuard>::iter_ordered`](crate::View::iter_ordered) for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map was not built with [`Builder::ordered`](crate::Builder::ordered).
+    #[inline]
+    pub fn iter_ordered(&self) -> impl Iterator<Item = (&K, &V)>
+    where
+        K: Hash + Eq,
+        S: BuildHasher,
+    {
+        self.guard.iter_ordered()
+    }
+
+    /// Returns a reference to the value corresponding to the key along with the entry's version.
+    ///
+    /// See [`View::<ReadGuard>::get_versioned`](crate::View::get_versioned) for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map was not built with [`Builder::versioned`](crate::Builder::versioned).
+    #[inline]
+    pub fn get_versioned<Q>(&self, key: &Q) -> Option<(&V, u64)>
+    where
+        K: Hash + Eq + Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        S: BuildHasher,
+    {
+        self.guard.get_versioned(key)
+    }
+}
+
+// TODO: It would probably be nicer if the write functionality got abstracted out into traits, but
+// that is a massive headache I don't want to deal with, so we're doing this for now.
+impl<'guard, K, V, S> View<WriteGuard<'guard, K, V, S>>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Inserts a key-value pair into the map.
+    ///
+    /// If the map did not have this key present, then `None` is returned. If it did, then the
+    /// evicted value is returned. See [`Evicted`](crate::Evicted) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// let mut guard = write.guard();
+    ///
+    /// assert!(guard.insert(17, "seven teen".to_owned()).is_none());
+    /// assert_eq!(&*guard.insert(17, "seventeen".to_owned()).unwrap(), "seven teen");
+    /// ```
+    #[inline]
+    pub fn insert<'ret>(&mut self, key: K, value: V) -> Option<Evicted<'ret, K, V>>
+    where
+        'guard: 'ret,
+    {
+        self.guard.insert(key, value)
+    }
+
+    /// Inserts a key-value pair into the map, discarding the evicted value if there is one.
+    ///
+    /// This is equivalent to ignoring the return value of [`insert`](crate::View::insert), but
+    /// does not construct an [`Evicted`](crate::Evicted). In either case, the evicted value is not
+    /// leaked: it is dropped once no readers can observe it anymore.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// let mut guard = write.guard();
+    ///
+    /// guard.set(17, "seven teen".to_owned());
+    /// guard.set(17, "seventeen".to_owned());
+    /// guard.publish();
+    ///
+    /// assert_eq!(read.guard().get(&17).unwrap(), "seventeen");
+    /// ```
+    #[inline]
+    pub fn set(&mut self, key: K, value: V) {
+        self.guard.set(key, value);
+    }
+
+    /// Inserts a key-value pair into the map without checking whether the key is already present.
+    ///
+    /// This skips the lookup performed by [`insert`](crate::View::insert), which makes it useful
+    /// for bulk-loading entries from a source which is known to contain no duplicate keys.
+    ///
+    /// # Safety
+    ///
+    /// The key must not already be present in the map, including keys inserted earlier through
+    /// this guard. The writer's map and the published map must always contain the same entries,
+    /// and later operations on a duplicated key may be replayed against a different entry than
+    /// the one they were applied to, which can lead to values being used after they are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    /// let mut guard = write.guard();
+    ///
+    /// for i in 0..100 {
+    ///     // Safety: every key is distinct, and the map started out empty
+    ///     unsafe { guard.insert_unique_unchecked(i, i * 2) };
+    /// }
+    /// guard.publish();
+    ///
+    /// let guard = read.guard();
+    /// assert_eq!(guard.len(), 100);
+    /// assert_eq!(guard.get(&21), Some(&42));
+    /// ```
+    #[inline]
+    pub unsafe fn insert_unique_unchecked(&mut self, key: K, value: V) {
+        unsafe { self.guard.insert_unique_unchecked(key, value) }
+    }
+
+    /// Reserves room for at least `additional` more changes to be recorded through this guard.
+    ///
+    /// Every change made through a write guard is recorded so that it can be applied to the other
+    /// copy of the map later. Reserving room for these records up front avoids reallocating them
+    /// repeatedly while making a large number of changes at once. This does not reserve any room
+    /// in the map itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    /// let mut guard = write.guard();
+    ///
+    /// guard.reserve_ops(1000);
+    /// for i in 0..1000 {
+    ///     guard.insert(i, i);
+    /// }
+    /// guard.publish();
+    ///
+    /// assert_eq!(read.guard().len(), 1000);
+    /// ```
+    #[inline]
+    pub fn reserve_ops(&mut self, additional: usize) {
+        self.guard.reserve_ops(additional);
+    }
+
+    /// Inserts a key-value pair into the map, returning whether the key was newly inserted along
+    /// with the evicted value, if any.
+    ///
+    /// This behaves exactly like [`insert`](crate::View::insert). Note that if the key was already
+    /// present, then only the value is replaced; the key stored in the map is left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// let mut guard = write.guard();
+    ///
+    /// let (fresh, evicted) = guard.insert_full(17, "seven teen".to_owned());
+    /// assert!(fresh);
+    /// assert!(evicted.is_none());
+    ///
+    /// let (fresh, evicted) = guard.insert_full(17, "seventeen".to_owned());
+    /// assert!(!fresh);
+    /// assert_eq!(&*evicted.unwrap(), "seven teen");
+    /// ```
+    #[inline]
+    pub fn insert_full<'ret>(&mut self, key: K, value: V) -> (bool, Option<Evicted<'ret, K, V>>)
+    where
+        'guard: 'ret,
+    {
+        let evicted = self.guard.insert(key, value);
+        (evicted.is_none(), evicted)
+    }
+
+    /// Inserts a key-value pair into the map, returning a reference to the value now in the map
+    /// along with the evicted value, if any.
+    ///
+    /// This behaves exactly like [`insert`](crate::View::insert), but avoids looking the key up
+    /// again to access the inserted value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// let mut guard = write.guard();
+    ///
+    /// let (value, evicted) = guard.insert_and_get(17, "seven teen".to_owned());
+    /// assert_eq!(value, "seven teen");
+    /// assert!(evicted.is_none());
+    ///
+    /// let (value, evicted) = guard.insert_and_get(17, "seventeen".to_owned());
+    /// assert_eq!(value, "seventeen");
+    /// assert_eq!(&*evicted.unwrap(), "seven teen");
+    /// ```
+    #[inline]
+    pub fn insert_and_get<'ret>(&mut self, key: K, value: V) -> (&V, Option<Evicted<'ret, K, V>>)
+    where
+        'guard: 'ret,
+    {
+        self.guard.insert_and_get(key, value)
+    }
+
+    /// Replaces the value associated with the given key according to the provided function.
+    ///
+    /// If the key is not present, then the function is not called, and `None` is returned. If the
+    /// key is present, then the function is called with the current value provided as the argument,
+    /// the value in the map is replaced, and the evicted value is returned. See
+    /// [`Evicted`](crate::Evicted) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// let mut guard = write.guard();
+    ///
+    /// guard.insert(1, "a".to_owned());
+    ///
+    /// // The key 0 is not in the map, so nothing changes
+    /// assert!(guard.replace(0, |_| String::new()).is_none());
+    /// assert_eq!(guard.get(&1).unwrap(), "a");
+    ///
+    /// // The key 1 is in the map, so the closure gets called
+    /// let evicted = guard.replace(1, |old| {
+    ///     assert_eq!(old, "a");
+    ///     "b".to_owned()
+    /// }).unwrap();
+    ///
+    /// // We evicted the old value "a"
+    /// assert_eq!(&*evicted, "a");
+    ///
+    /// // And now "b" is in the map
+    /// assert_eq!(guard.get(&1).unwrap(), "b");
+    /// ```
+    #[inline]
+    pub fn replace<'ret, F>(&mut self, key: K, op: F) -> Option<Evicted<'ret, K, V>>
+    where
+        F: FnOnce(&V) -> V,
+        'guard: 'ret,
+    {
+        self.guard.replace(key, op)
+    }
+
+    /// Like [`replace`](crate::View::replace), but with a fallible function.
+    ///
+    /// If the key is not present, then the function is not called, and `Ok(None)` is returned. If
+    /// the function returns an error, then the map is left unchanged and the error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use std::num::ParseIntError;
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// let mut guard = write.guard();
+    ///
+    /// guard.insert(1, "41".to_owned());
+    ///
+    /// let increment = |old: &String| -> Result<String, ParseIntError> {
+    ///     Ok((old.parse::<u32>()? + 1).to_string())
+    /// };
+    ///
+    /// let evicted = guard.try_replace(1, increment).unwrap().unwrap();
+    /// assert_eq!(&*evicted, "41");
+    /// assert_eq!(guard.get(&1).unwrap(), "42");
+    ///
+    /// // The map is left unchanged if the function fails
+    /// guard.insert(2, "two".to_owned());
+    /// assert!(guard.try_replace(2, increment).is_err());
+    /// assert_eq!(guard.get(&2).unwrap(), "two");
+    ///
+    /// // The function is not called if the key is not present
+    /// assert!(guard.try_replace(3, increment).unwrap().is_none());
+    /// ```
+    #[inline]
+    pub fn try_replace<'ret, F, E>(
+        &mut self,
+        key: K,
+        op: F,
+    ) -> Result<Option<Evicted<'ret, K, V>>, E>
+    where
+        F: FnOnce(&V) -> Result<V, E>,
+        'guard: 'ret,
+    {
+        self.guard.try_replace(key, op)
+    }
+
+    /// Replaces the value of the given key, but only if the entry's version is still `expected`.
+    /// This requires the map to have been built with
+    /// [`Builder::versioned`](crate::Builder::versioned).
+    ///
+    /// The expected version is typically obtained by a reader via
+    /// [`get_versioned`](crate::View::get_versioned). If the entry was changed or removed since
+    /// then, including by earlier writes through this guard, then the map is left untouched and a
+    /// [`VersionMismatch`](crate::VersionMismatch) is returned. Otherwise the evicted value is
+    /// returned, and the entry is assigned a new version.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map was not built with [`Builder::versioned`](crate::Builder::versioned).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::Builder;
+    ///
+    /// let (mut write, read) = Builder::new().versioned().build::<u32, String>();
+    /// write.guard().insert(1, "a".to_owned());
+    ///
+    /// let version = read.guard().get_versioned(&1).unwrap().1;
+    ///
+    /// let mut guard = write.guard();
+    /// let evicted = guard.replace_if_version(1, version, "b".to_owned()).unwrap();
+    /// assert_eq!(*evicted, "a");
+    ///
+    /// // The entry was changed, so the version is stale
+    /// let mismatch = guard.replace_if_version(1, version, "c".to_owned()).err().unwrap();
+    /// assert_ne!(mismatch.current(), Some(version));
+    /// assert_eq!(guard.get(&1).unwrap(), "b");
+    /// ```
+    #[inline]
+    pub fn replace_if_version<'ret>(
+        &mut self,
+        key: K,
+        expected: u64,
+        value: V,
+    ) -> Result<Evicted<'ret, K, V>, VersionMismatch>
+    where
+        'guard: 'ret,
+    {
+        self.guard.replace_if_version(key, expected, value)
+    }
+
+    /// Removes a key from the map, returning the value at the key if the key was previously in the
+    /// map. See [`Evicted`](crate::Evicted) for details on accessing the removed value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// let mut guard = write.guard();
+    ///
+    /// guard.insert(0, "a".to_owned());
+    ///
+    /// assert_eq!(&*guard.remove(0).unwrap(), "a");
+    /// assert!(guard.remove(0).is_none());
+    /// assert!(guard.remove(1).is_none());
+    /// ```
+    #[inline]
+    pub fn remove<'ret>(&mut self, key: K) -> Option<Evicted<'ret, K, V>>
+    where
+        'guard: 'ret,
+    {
+        self.guard.remove(key)
+    }
+
+    /// Removes each of the given keys from the map, returning how many of them were present.
+    ///
+    /// The removed values are dropped once it is safe to do so. If you need access to the removed
+    /// values, use [`remove`](crate::View::remove) instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    /// let mut guard = write.guard();
+    ///
+    /// for i in 0..5 {
+    ///     guard.insert(i, i);
+    /// }
+    ///
+    /// assert_eq!(guard.remove_many([1, 3, 5]), 2);
+    /// guard.publish();
+    ///
+    /// assert_eq!(read.guard().len(), 3);
+    /// ```
+    #[inline]
+    pub fn remove_many<I>(&mut self, keys: I) -> usize
+    where
+        I: IntoIterator<Item = K>,
+    {
+        keys.into_iter()
+            .filter_map(|key| self.guard.remove(key))
+            .count()
+    }
+
+    /// Applies each of the given operations to the map, in order.
+    ///
+    /// This is equivalent to calling [`insert`](crate::View::insert),
+    /// [`replace`](crate::View::replace), [`remove`](crate::View::remove) or
+    /// [`clear_and_shrink`](crate::View::clear_and_shrink) for each operation.
+    /// Any evicted values are dropped once it is safe to do so.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::MapOp;
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, &str>();
+    ///
+    /// let log = vec![
+    ///     MapOp::Insert(1, "a"),
+    ///     MapOp::Insert(2, "b"),
+    ///     MapOp::Replace(1, "c"),
+    ///     MapOp::Replace(3, "d"),
+    ///     MapOp::Remove(2),
+    /// ];
+    /// write.guard().apply(log);
+    ///
+    /// let guard = read.guard();
+    /// assert_eq!(guard.len(), 1);
+    /// assert_eq!(*guard.get(&1).unwrap(), "c");
+    /// ```
+    pub fn apply<I>(&mut self, ops: I)
+    where
+        I: IntoIterator<Item = MapOp<K, V>>,
+    {
+        for op in ops {
+            match op {
+                MapOp::Insert(key, value) => drop(self.guard.insert(key, value)),
+                MapOp::Replace(key, value) => drop(self.guard.replace(key, |_| value)),
+                MapOp::Remove(key) => drop(self.guard.remove(key)),
+                MapOp::Clear => self.guard.clear_and_shrink(0),
+            }
+        }
+    }
+
+    /// Returns a mutable proxy for the value corresponding to the key, allowing it to be edited
+    /// "in place."
+    ///
+    /// Since readers may still be viewing the current value, it cannot actually be mutated.
+    /// Instead, the returned [`ValueMut`](crate::ValueMut) holds a clone of the value, and when it
+    /// is dropped the clone replaces the value in the map as if by
+    /// [`replace`](crate::View::replace). This means that every call clones the value and records
+    /// a replacement, even if the value is never modified. For cheap-to-clone values this is a
+    /// convenient alternative to `replace`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<String, u32>();
+    /// let mut guard = write.guard();
+    ///
+    /// guard.insert("hits".to_owned(), 0);
+    /// *guard.get_mut("hits").unwrap() += 1;
+    /// *guard.get_mut("hits").unwrap() += 1;
+    /// assert!(guard.get_mut("misses").is_none());
+    ///
+    /// assert_eq!(guard.get("hits"), Some(&2));
+    /// guard.publish();
+    ///
+    /// assert_eq!(read.guard().get("hits"), Some(&2));
+    /// ```
+    #[inline]
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<ValueMut<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.guard.get_mut(key)
+    }
+
+    /// Returns a reference to the value associated with the given key, inserting the result of
+    /// calling the given function on the key if it is not present.
+    ///
+    /// The function is only called if the key is not in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<String, usize>();
+    /// let mut guard = write.guard();
+    ///
+    /// assert_eq!(*guard.get_or_insert_with_key("ferris".to_owned(), String::len), 6);
+    /// assert_eq!(*guard.get_or_insert_with_key("ferris".to_owned(), |_| unreachable!()), 6);
+    /// guard.publish();
+    ///
+    /// assert_eq!(read.guard().get("ferris"), Some(&6));
+    /// ```
+    #[inline]
+    pub fn get_or_insert_with_key<F>(&mut self, key: K, op: F) -> &V
+    where
+        F: FnOnce(&K) -> V,
+    {
+        self.guard.get_or_insert_with_key(key, op)
+    }
+
+    /// Returns a reference to the value associated with the given key, inserting the value
+    /// produced by the given fallible function if the key is not present.
+    ///
+    /// The key is only hashed once, and the function is only called if the key is not in the map.
+    /// If the function returns an error, then the map is left unchanged and the error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<String, u32>();
+    /// let mut guard = write.guard();
+    ///
+    /// let value = guard.get_or_try_insert_with("answer".to_owned(), || "42".parse());
+    /// assert_eq!(value, Ok(&42));
+    ///
+    /// let value = guard.get_or_try_insert_with("question".to_owned(), || "?".parse::<u32>());
+    /// assert!(value.is_err());
+    /// assert!(!guard.contains_key("question"));
+    /// guard.publish();
+    ///
+    /// assert_eq!(read.guard().len(), 1);
+    /// ```
+    #[inline]
+    pub fn get_or_try_insert_with<F, E>(&mut self, key: K, op: F) -> Result<&V, E>
+    where
+        F: FnOnce() -> Result<V, E>,
+    {
+        self.guard.get_or_try_insert_with_key(key, |_| op())
+    }
+
+    /// Replaces every value in the map with the result of calling the given function on its
+    /// entry.
+    ///
+    /// This is equivalent to calling [`replace`](crate::View::replace) for every key in the map,
+    /// but avoids looking up each key again. The old values are dropped once it is safe to do so.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// let mut guard = write.guard();
+    ///
+    /// guard.insert(1, "a".to_owned());
+    /// guard.insert(2, "b".to_owned());
+    ///
+    /// guard.map_values(|key, value| format!("{value}{key}"));
+    /// guard.publish();
+    ///
+    /// let guard = read.guard();
+    /// assert_eq!(guard.get(&1).unwrap(), "a1");
+    /// assert_eq!(guard.get(&2).unwrap(), "b2");
+    /// ```
+    #[inline]
+    pub fn map_values<F>(&mut self, op: F)
+    where
+        F: FnMut(&K, &V) -> V,
+    {
+        self.guard.map_values(op)
+    }
+
+    /// Visits every entry in the map, removing it if the given function returns `None`, and
+    /// replacing its value if the function returns `Some`.
+    ///
+    /// This combines [`extract_if`](crate::View::extract_if) and
+    /// [`map_values`](crate::View::map_values) into a single pass over the map, which is useful
+    /// for sweeps that expire some entries and refresh others. The removed and replaced values are
+    /// dropped once it is safe to do so.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<&str, u32>();
+    /// let mut guard = write.guard();
+    ///
+    /// guard.insert("fresh", 1);
+    /// guard.insert("stale", 5);
+    /// guard.insert("expired", 10);
+    ///
+    /// // Age every entry, expiring those which get too old
+    /// guard.retain_map(|_, &age| (age < 10).then(|| age + 1));
+    /// guard.publish();
+    ///
+    /// let guard = read.guard();
+    /// assert_eq!(guard.len(), 2);
+    /// assert_eq!(*guard.get("fresh").unwrap(), 2);
+    /// assert_eq!(*guard.get("stale").unwrap(), 6);
+    /// ```
+    #[inline]
+    pub fn retain_map<F>(&mut self, op: F)
+    where
+        F: FnMut(&K, &V) -> Option<V>,
+    {
+        self.guard.retain_map(op)
+    }
+
+    /// Removes every entry from the map, and then resizes the map to hold `capacity` entries
+    /// without reallocating.
+    ///
+    /// The removed entries are dropped once it is safe to do so. Both copies of the map are
+    /// resized, which is useful for bringing a map which once grew very large back down to a
+    /// small steady state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
+    /// let mut guard = write.guard();
+    /// for i in 0..1000 {
+    ///     guard.insert(i, i);
+    /// }
+    /// guard.publish();
+    ///
+    /// let mut guard = write.guard();
+    /// guard.clear_and_shrink(16);
+    /// assert!(guard.is_empty());
+    /// guard.publish();
+    ///
+    /// assert!(read.guard().is_empty());
+    /// ```
+    #[inline]
+    pub fn clear_and_shrink(&mut self, capacity: usize) {
+        self.guard.clear_and_shrink(capacity)
+    }
+
+    /// Removes every entry for which the given predicate returns `true`, returning the removed
+    /// keys along with their evicted values. See [`Evicted`](crate::Evicted) for details on
+    /// accessing the removed values.
+    ///
+    /// The keys in the map may still be visible to readers, so a clone of each removed key is
+    /// returned rather than the key itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// let mut guard = write.guard();
+    ///
+    /// for i in 0..6 {
+    ///     guard.insert(i, i.to_string());
+    /// }
+    ///
+    /// let mut odd = guard.extract_if(|&key, _| key % 2 == 1);
+    /// odd.sort_by_key(|(key, _)| *key);
+    ///
+    /// assert_eq!(odd.len(), 3);
+    /// assert_eq!(odd[0].0, 1);
+    /// assert_eq!(&*odd[2].1, "5");
+    /// assert_eq!(guard.len(), 3);
+    /// assert!(!guard.contains_key(&3));
+    /// ```
+    #[inline]
+    pub fn extract_if<'ret, F>(&mut self, pred: F) -> Vec<(K, Evicted<'ret, K, V>)>
+    where
+        F: FnMut(&K, &V) -> bool,
+        K: Clone,
+        'guard: 'ret,
+    {
+        self.guard.extract_if(pred)
+    }
+
+    /// Creates a raw entry builder for the map, mirroring `hashbrown`'s raw entry API.
+    ///
+    /// This is meant for advanced use, such as searching with a precomputed hash. All
+    /// modifications made through the returned entries are recorded just like those made through
+    /// the other methods on this type. Operations which rely on the caller to uphold the map's
+    /// invariants are `unsafe`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::{self, RawEntryMut};
+    ///
+    /// let (mut write, read) = flashmap::new::<String, u32>();
+    /// let mut guard = write.guard();
+    ///
+    /// match guard.raw_entry_mut().from_key("apples") {
+    ///     RawEntryMut::Occupied(_) => unreachable!(),
+    ///     // Safety: we're inserting the key we looked up
+    ///     RawEntryMut::Vacant(entry) => unsafe { entry.insert("apples".to_owned(), 1); },
+    /// }
+    ///
+    /// match guard.raw_entry_mut().from_key("apples") {
+    ///     RawEntryMut::Occupied(mut entry) => {
+    ///         let old = entry.insert(2);
+    ///         assert_eq!(*old, 1);
+    ///     }
+    ///     RawEntryMut::Vacant(_) => unreachable!(),
+    /// }
+    ///
+    /// guard.publish();
+    /// assert_eq!(*read.guard().get("apples").unwrap(), 2);
+    /// ```
+    #[inline]
+    pub fn raw_entry_mut(&mut self) -> RawEntryBuilderMut<'_, K, V, S> {
+        self.guard.raw_entry_mut()
+    }
+
+    /// Gets the entry for the given key, which may be a borrowed form of the map's key type.
+    ///
+    /// Unlike [`insert`](crate::View::insert), this does not require an owned key up front. The
+    /// key is only converted into an owned key if a value is inserted into a vacant entry, which
+    /// avoids needless allocations for keys such as `String` when the entry is already occupied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::{self, EntryRef};
+    ///
+    /// let (mut write, read) = flashmap::new::<String, u32>();
+    /// let mut guard = write.guard();
+    ///
+    /// // The key is only allocated here since the entry is vacant
+    /// assert_eq!(*guard.entry_ref("apples").or_insert(1), 1);
+    /// assert_eq!(*guard.entry_ref("apples").or_insert(2), 1);
+    ///
+    /// match guard.entry_ref("apples") {
+    ///     EntryRef::Occupied(mut entry) => {
+    ///         let old = entry.insert(3);
+    ///         assert_eq!(*old, 1);
+    ///     }
+    ///     EntryRef::Vacant(_) => unreachable!(),
+    /// }
+    ///
+    /// guard.publish();
+    /// assert_eq!(*read.guard().get("apples").unwrap(), 3);
+    /// ```
+    #[inline]
+    pub fn entry_ref<'q, Q>(&mut self, key: &'q Q) -> EntryRef<'_, 'q, K, Q, V, S>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        self.guard.entry_ref(key)
+    }
+
+    /// Takes ownership of a leaked value and drops the inner value when it is safe to do so.
+    ///
+    /// There are no guarantees regarding when the leaked value will be dropped. It is only
+    /// guaranteed that it will eventually be dropped provided that no handles or guards
+    /// associated with this map are leaked or forgotten.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided leaked value came from a different map then the one this guard is
+    /// associated with. Note that it is **not** required that this method is called with the same
+    /// guard that created the leaked value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap::{self, Evicted};
+    /// let (mut write, read) = flashmap::new::<String, String>();
+    ///
+    /// write.guard().insert("ferris".to_owned(), "crab".to_owned());
+    ///
+    /// // ~~ stuff happens ~~
+    ///
+    /// let mut guard = write.guard();
+    ///
+    /// // Remove the value and leak it
+    /// let leaked = guard.remove("ferris".to_owned())
+    ///     .map(Evicted::leak)
+    ///     .unwrap();
+    /// assert_eq!(&*leaked, "crab");
+    ///
+    /// // We decide we don't need to keep the leaked value around, so we tell the guard
+    /// // to drop it when it is safe to do so
+    /// guard.drop_lazily(leaked);
+    ///
+    /// guard.publish();
+    /// ```
+    #[inline]
+    pub fn drop_lazily(&self, leaked: Leaked<V>) {
+        self.guard.drop_lazily(leaked)
+    }
+
+    /// Undoes [`Evicted::leak`](crate::Evicted::leak), handing the value back to the map so that
+    /// it is dropped automatically, as if it had never been leaked.
+    ///
+    /// This is equivalent to [`drop_lazily`](crate::View::drop_lazily), and exists so that code
+    /// which leaks a value and later changes its mind reads symmetrically.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided leaked value came from a different map then the one this guard is
+    /// associated with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap::{self, Evicted};
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    ///
+    /// write.guard().insert(1, "one".to_owned());
+    ///
+    /// let mut guard = write.guard();
+    /// let leaked = guard.remove(1).map(Evicted::leak).unwrap();
+    ///
+    /// // On second thought, we don't need the value after all
+    /// guard.unleak(leaked);
+    /// guard.publish();
+    /// ```
+    #[inline]
+    pub fn unleak(&self, leaked: Leaked<V>) {
+        self.guard.drop_lazily(leaked)
+    }
+
+    /// Consumes this view and its guard, publishing all previous changes to the map.
+    ///
+    /// This has the same effect as dropping the view. Note that the changes will only be visible
+    /// through newly created read or write guards.
+    ///
+    /// If the guard was created through a [`BatchGuard`](crate::BatchGuard), then this does
+    /// nothing, and changes are instead published when the batch guard is dropped. Likewise, if no
+    /// changes were made through the guard, then nothing is published, and readers are not moved
+    /// to the other copy of the map.
+    #[inline]
+    pub fn publish(self) {
+        self.guard.publish()
+    }
+
+    /// Consumes this view and its guard, publishing all previous changes to the map, and then
+    /// blocks until every reader sees the new version of the map.
+    ///
+    /// This is equivalent to calling [`publish`](crate::View::publish) followed by
+    /// [`WriteHandle::synchronize`](crate::WriteHandle::synchronize). Once this returns, no read
+    /// guard is viewing the map as it was before the publish. Note that if the calling thread
+    /// holds a read guard created before this call, then this will deadlock.
+    ///
+    /// If the guard was created through a [`BatchGuard`](crate::BatchGuard), then this does
+    /// nothing, like [`publish`](crate::View::publish).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use std::{sync::mpsc, thread};
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    /// let (tx, rx) = mpsc::channel();
+    ///
+    /// let reader = thread::spawn(move || {
+    ///     let guard = read.guard();
+    ///     tx.send(()).unwrap();
+    ///     assert!(guard.get(&1).is_none());
+    ///     // The writer cannot finish synchronizing until this guard is dropped
+    ///     drop(guard);
+    ///     read
+    /// });
+    ///
+    /// rx.recv().unwrap();
+    /// let mut guard = write.guard();
+    /// guard.insert(1, 2);
+    /// guard.publish_and_synchronize();
+    ///
+    /// // Every reader now sees the insertion
+    /// let read = reader.join().unwrap();
+    /// assert_eq!(*read.guard().get(&1).unwrap(), 2);
+    /// ```
+    #[inline]
+    pub fn publish_and_synchronize(self) {
+        self.guard.publish_and_synchronize()
     }
 }
@@ -1,21 +1,48 @@
 use std::{
-    collections::hash_map::RandomState,
+    borrow::Borrow,
+    collections::{hash_map::RandomState, HashMap},
+    convert::Infallible,
+    future::Future,
     hash::{BuildHasher, Hash},
-    mem,
+    hint, mem,
     num::NonZeroUsize,
-    ops::Deref,
-    sync::atomic::{AtomicUsize, Ordering},
+    ops::{Deref, DerefMut},
+    rc::Rc,
+    sync::atomic::Ordering,
+    time::Duration,
 };
 
-use hashbrown::hash_map::RawEntryMut;
+#[cfg(feature = "portable-atomic")]
+use portable_atomic::AtomicUsize;
+#[cfg(not(feature = "portable-atomic"))]
+use std::sync::atomic::AtomicUsize;
+
+use hashbrown::hash_map;
+
+mod entry_ref;
+mod observer;
+mod order;
+mod raw_entry;
+mod synchronize;
+mod version;
+
+pub use entry_ref::*;
+use observer::Observer;
+use order::OrderTracker;
+pub(crate) use order::{iter_ordered, InsertionOrder};
+pub use raw_entry::*;
+pub use synchronize::SynchronizeTimeout;
+pub use version::VersionMismatch;
+use version::VersionTracker;
+pub(crate) use version::{get_versioned, EntryVersions};
 
 use crate::{
     core::Core,
-    loom::cell::UnsafeCell,
+    loom::cell::{Cell, UnsafeCell},
     loom::sync::Arc,
     util::{Alias, BorrowHelper},
     view::sealed::ReadAccess,
-    Map, View,
+    Map, ReadHandle, View,
 };
 
 static NEXT_WRITER_UID: AtomicUsize = AtomicUsize::new(1);
@@ -44,7 +71,11 @@ where
     S: BuildHasher,
 {
     core: Arc<Core<K, V, S>>,
-    operations: UnsafeCell<Vec<Operation<K, V>>>,
+    operations: UnsafeCell<OperationLog<K, V>>,
+    order: Option<UnsafeCell<OrderTracker<K>>>,
+    versions: Option<UnsafeCell<VersionTracker<K>>>,
+    last_publish_changed: Cell<bool>,
+    observer: Option<Observer<K, V>>,
     uid: WriterUid,
 }
 
@@ -62,13 +93,33 @@ where
     S: BuildHasher,
 {
     pub(crate) unsafe fn new(core: Arc<Core<K, V, S>>) -> Self {
+        #[cfg(debug_assertions)]
+        core.debug_claim_writer();
+
+        let order = core
+            .is_ordered()
+            .then(|| UnsafeCell::new(OrderTracker::new()));
+        let versions = core
+            .is_versioned()
+            .then(|| UnsafeCell::new(VersionTracker::new()));
+        let operations = OperationLog::new(core.reclaims_eagerly());
+
         Self {
             core,
-            operations: UnsafeCell::new(Vec::new()),
+            operations: UnsafeCell::new(operations),
+            order,
+            versions,
+            last_publish_changed: Cell::new(false),
+            observer: None,
             uid: WriterUid::next(),
         }
     }
 
+    #[cfg(test)]
+    pub(crate) fn core(&self) -> &Core<K, V, S> {
+        &self.core
+    }
+
     /// Blocks the calling thread until all readers see the same version of the map.
     ///
     /// If all readers already see the same version of the map (or if there are no active readers)
@@ -81,6 +132,319 @@ where
         self.core.synchronize();
     }
 
+    /// Like [`synchronize`](crate::WriteHandle::synchronize), but gives up if not all readers
+    /// see the same version of the map after the given timeout. Returns `true` if synchronization
+    /// completed, and `false` if it timed out.
+    ///
+    /// If a reader holds onto a guard indefinitely (for instance if it was leaked), then
+    /// `synchronize` and [`guard`](crate::WriteHandle::guard) will block forever. This method can
+    /// be used to detect that situation before committing to a blocking call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use std::time::Duration;
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
+    /// let guard = read.guard();
+    /// write.guard().insert(1, 2);
+    ///
+    /// // The reader is still viewing the old version of the map
+    /// assert!(!write.synchronize_timeout(Duration::from_millis(10)));
+    ///
+    /// drop(guard);
+    /// assert!(write.synchronize_timeout(Duration::from_millis(10)));
+    /// ```
+    #[inline]
+    pub fn synchronize_timeout(&self, timeout: Duration) -> bool {
+        self.core.synchronize_timeout(timeout)
+    }
+
+    /// Returns a future which waits for all readers to see the same version of the map, without
+    /// blocking the current thread. The future resolves to `true` if synchronization completed,
+    /// and to `false` if the given timer finished first.
+    ///
+    /// The timer can be any future, such as the sleep future of whichever async runtime is in
+    /// use, so this method does not depend on a particular runtime. It is only polled while
+    /// readers are still viewing an older version of the map. Dropping the returned future before
+    /// it resolves abandons the wait. However, leaking it with [`mem::forget`](std::mem::forget)
+    /// while it is waiting may cause later synchronization to block forever.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// # use std::{future::Future, pin::pin, sync::Arc, task::{Context, Poll, Wake}, thread};
+    /// # struct ThreadWaker(thread::Thread);
+    /// # impl Wake for ThreadWaker {
+    /// #     fn wake(self: Arc<Self>) { self.0.unpark(); }
+    /// # }
+    /// # fn block_on<F: Future>(future: F) -> F::Output {
+    /// #     let mut future = pin!(future);
+    /// #     let waker = Arc::new(ThreadWaker(thread::current())).into();
+    /// #     let mut cx = Context::from_waker(&waker);
+    /// #     loop {
+    /// #         match future.as_mut().poll(&mut cx) {
+    /// #             Poll::Ready(output) => return output,
+    /// #             Poll::Pending => thread::park(),
+    /// #         }
+    /// #     }
+    /// # }
+    /// use std::future;
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
+    /// let guard = read.guard();
+    /// write.guard().insert(1, 2);
+    ///
+    /// // The reader is still viewing the old version of the map, and the timer has already
+    /// // finished
+    /// assert!(!block_on(write.synchronize_timeout_async(future::ready(()))));
+    ///
+    /// drop(guard);
+    /// assert!(block_on(write.synchronize_timeout_async(future::pending::<()>())));
+    /// ```
+    #[inline]
+    pub fn synchronize_timeout_async<T>(&mut self, timer: T) -> SynchronizeTimeout<'_, K, V, S, T>
+    where
+        T: Future,
+    {
+        SynchronizeTimeout::new(self, timer)
+    }
+
+    /// Returns the IDs of the threads which created read handles that may be preventing the
+    /// writer from making progress.
+    ///
+    /// If all readers already see the latest version of the map, then the returned list is empty.
+    /// Otherwise, it contains an entry for every read handle with live guards, identified by the
+    /// thread on which that handle was created. Some of those guards may already be viewing the
+    /// latest version, so this is an over-approximation meant for debugging stalls in
+    /// [`synchronize`](crate::WriteHandle::synchronize).
+    ///
+    /// This method is only available with the `diagnostics` feature enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use std::thread;
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    /// assert!(write.lagging_readers().is_empty());
+    ///
+    /// let read_guard = read.guard();
+    /// write.guard().insert(1, 1);
+    ///
+    /// // The read guard is still viewing the old version of the map
+    /// assert_eq!(write.lagging_readers(), [thread::current().id()]);
+    ///
+    /// drop(read_guard);
+    /// assert!(write.lagging_readers().is_empty());
+    /// ```
+    #[cfg(feature = "diagnostics")]
+    pub fn lagging_readers(&self) -> Vec<std::thread::ThreadId> {
+        self.core.lagging_readers()
+    }
+
+    /// Returns whether or not both copies of the map contain the same entries.
+    ///
+    /// Changes are only applied to the second copy of the map when the writer next accesses it,
+    /// so the copies diverge after every publish which changed the map, and converge again once
+    /// a new write guard is created or [`flush`](crate::WriteHandle::flush) is called. This only
+    /// exists for testing the crate itself, and is not part of the public API.
+    #[doc(hidden)]
+    #[cfg(feature = "diagnostics")]
+    pub fn maps_converged(&self) -> bool
+    where
+        V: PartialEq,
+    {
+        // Safety: the writer only mutates either map through a guard, which requires mutable
+        // access to this handle, and readers never mutate the maps
+        self.core.writer_map().with(|writer_ptr| {
+            self.core.published_map().with(|published_ptr| {
+                let (writer, published) = unsafe { (&*writer_ptr, &*published_ptr) };
+                writer.len() == published.len()
+                    && writer
+                        .iter()
+                        .all(|(key, value)| matches!(published.get(key), Some(other) if **other == **value))
+            })
+        })
+    }
+
+    /// Returns the number of read guards which are currently alive, across all read handles.
+    ///
+    /// Since readers create and drop guards concurrently with the writer, this is only a racy
+    /// snapshot which may be outdated by the time it is returned. It is meant as a heuristic, for
+    /// instance to decide whether to publish eagerly or to batch more changes while many readers
+    /// would have to be waited on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    /// assert_eq!(write.open_guards(), 0);
+    ///
+    /// let read2 = read.clone();
+    /// let guard1 = read.guard();
+    /// let guard2 = read.guard();
+    /// let guard3 = read2.guard_owned();
+    /// assert_eq!(write.open_guards(), 3);
+    ///
+    /// drop((guard1, guard2, guard3));
+    /// assert_eq!(write.open_guards(), 0);
+    /// ```
+    pub fn open_guards(&self) -> usize {
+        self.core.open_guards()
+    }
+
+    /// Returns whether or not the given read handle reads from the map owned by this handle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (write, read) = flashmap::new::<u32, u32>();
+    /// let (other_write, _other_read) = flashmap::new::<u32, u32>();
+    ///
+    /// assert!(write.owns(&read));
+    /// assert!(!other_write.owns(&read));
+    /// ```
+    #[inline]
+    pub fn owns(&self, read: &ReadHandle<K, V, S>) -> bool {
+        read.reads_from(&self.core)
+    }
+
+    /// Returns whether or not the map contains the given key, without creating a guard.
+    ///
+    /// This reflects all writes made through this handle so far, and does not need to wait for
+    /// readers to move off of the writer's map, making it cheaper than
+    /// `write.guard().contains_key(key)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<String, String>();
+    ///
+    /// if !write.contains_key("ferris") {
+    ///     write.guard().insert("ferris".to_owned(), "crab".to_owned());
+    /// }
+    ///
+    /// assert!(write.contains_key("ferris"));
+    /// assert!(!write.contains_key("corro"));
+    /// ```
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.peek(|view| view.contains_key(key))
+    }
+
+    /// Provides read-only access to the map as this handle sees it, without creating a write
+    /// guard.
+    ///
+    /// Unlike [`guard`](crate::WriteHandle::guard), this does not wait for readers to move off of
+    /// the writer's map, nor does it touch the operation log, so it is the cheaper option when
+    /// the writer only needs to read. The view reflects all writes made through this handle so
+    /// far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
+    /// write.guard().insert(1, 10);
+    ///
+    /// // Decide whether or not we need to write at all
+    /// let current = write.peek(|view| view.get(&1).copied());
+    /// if current != Some(10) {
+    ///     write.guard().insert(1, 10);
+    /// }
+    ///
+    /// assert_eq!(write.peek(|view| view.len()), 1);
+    /// ```
+    #[inline]
+    pub fn peek<F, R>(&self, op: F) -> R
+    where
+        F: FnOnce(&View<PeekGuard<'_, K, V, S>>) -> R,
+    {
+        op(&View::new(PeekGuard {
+            map: self.core.published_map(),
+        }))
+    }
+
+    /// Returns a clone of the value associated with the given key, computing and publishing it
+    /// first if it is not present.
+    ///
+    /// If the key is present, this does not create a guard, so it is as cheap as
+    /// [`peek`](crate::WriteHandle::peek). Otherwise, `f` is called to compute the value, which is
+    /// inserted and immediately published, so it is visible to all new read guards once this
+    /// returns. Like [`guard`](crate::WriteHandle::guard), this may block while waiting for
+    /// readers to move off of the writer's map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    ///
+    /// let value = write.get_or_compute(1, || "one".to_owned());
+    /// assert_eq!(value, "one");
+    /// assert_eq!(read.guard().get(&1).unwrap(), "one");
+    ///
+    /// // The value is already cached, so the function is not called
+    /// let value = write.get_or_compute(1, || unreachable!());
+    /// assert_eq!(value, "one");
+    /// ```
+    pub fn get_or_compute<F>(&mut self, key: K, f: F) -> V
+    where
+        V: Clone,
+        F: FnOnce() -> V,
+    {
+        if let Some(value) = self.peek(|view| view.get(&key).cloned()) {
+            return value;
+        }
+
+        let value = f();
+        let mut guard = self.guard();
+        guard.insert(key, value.clone());
+        guard.publish();
+        value
+    }
+
+    /// Estimates the number of bytes used by the two internal copies of the map.
+    ///
+    /// This accounts for the tables storing the entries, based on their current capacity, but
+    /// not for any heap memory owned by the keys and values themselves (such as the contents of a
+    /// `String`). The actual allocation may differ slightly depending on the platform.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u64, u64>();
+    /// let empty = write.memory_usage();
+    ///
+    /// let mut guard = write.guard();
+    /// for i in 0..1000 {
+    ///     guard.insert(i, i);
+    /// }
+    /// guard.publish();
+    ///
+    /// // Both copies of the map hold 1000 entries of 16 bytes each
+    /// write.guard();
+    /// assert!(write.memory_usage() >= empty + 2 * 1000 * 16);
+    /// ```
+    pub fn memory_usage(&self) -> usize {
+        self.core.memory_usage()
+    }
+
     /// Creates a new [`WriteGuard`](crate::WriteGuard) wrapped in a [`View`](crate::View),
     /// allowing for safe read and write access to the map.
     ///
@@ -125,7 +489,609 @@ where
     /// assert!(!guard.contains_key("apple"));
     /// ```
     pub fn guard(&mut self) -> View<WriteGuard<'_, K, V, S>> {
+        let map = self.prepare_writer_map();
+
+        View::new(WriteGuard {
+            map,
+            handle: self,
+            handle_uid: self.uid,
+            publish_on_drop: true,
+        })
+    }
+
+    /// Creates a new [`BatchGuard`](crate::BatchGuard), which allows changes from multiple write
+    /// guards to be published all at once.
+    ///
+    /// Every time a [`WriteGuard`](crate::WriteGuard) is dropped, readers are moved over to the
+    /// newly written map, and the next write guard has to wait for all of them to finish with the
+    /// old one. If many small, separate edits are made in quick succession, this can be wasteful.
+    /// Write guards created through a batch guard do not publish when dropped; instead all of
+    /// their changes are published together when the batch guard is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
+    /// let mut batch = write.batch();
+    ///
+    /// for i in 0..3 {
+    ///     // Each guard is dropped at the end of this statement without publishing
+    ///     batch.guard().insert(i, i * i);
+    /// }
+    ///
+    /// // Nothing has been published yet
+    /// assert!(read.guard().is_empty());
+    ///
+    /// // Publish everything at once
+    /// batch.publish();
+    /// assert_eq!(read.guard().len(), 3);
+    /// ```
+    pub fn batch(&mut self) -> BatchGuard<'_, K, V, S> {
+        let map = self.prepare_writer_map();
+
+        BatchGuard { map, handle: self }
+    }
+
+    /// Sets a function which is called with every change made to the map from now on, such as
+    /// for mirroring the changes to a write-ahead log. This replaces any previously set function.
+    ///
+    /// Each change is described by a [`MapOp`](crate::MapOp) which borrows the key and value in
+    /// the map, and which can be replayed on another map via [`View::apply`](crate::View::apply)
+    /// once converted into an owned operation. Changes which do not modify the map, such as
+    /// replacing the value of a key which is not present, are not reported.
+    ///
+    /// Changes are reported in the order they were made when they are published, before they
+    /// become visible to readers, rather than as they are made. A change made through a write
+    /// guard is therefore reported when that guard (or the enclosing
+    /// [`BatchGuard`](crate::BatchGuard)) is dropped or published. This way the function sees
+    /// exactly the changes which readers are about to see, all at once, so a log only has to be
+    /// flushed once per publish. It also covers every way of changing the map, including entries
+    /// and [`retain_map`](crate::View::retain_map), and the function never runs while a write
+    /// guard is changing the map.
+    ///
+    /// The function runs on the writer's thread while it publishes, so it should not block for
+    /// long, and it must not access the map. If the function panics, the process is aborted,
+    /// since the changes can no longer be published or discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::MapOp;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    ///
+    /// let log = Arc::new(Mutex::new(Vec::new()));
+    /// let log2 = Arc::clone(&log);
+    /// write.set_op_observer(move |op| {
+    ///     // Record an owned copy of each change
+    ///     let op = match *op {
+    ///         MapOp::Insert(&key, &value) => MapOp::Insert(key, value),
+    ///         MapOp::Replace(&key, &value) => MapOp::Replace(key, value),
+    ///         MapOp::Remove(&key) => MapOp::Remove(key),
+    ///         MapOp::Clear => MapOp::Clear,
+    ///         _ => unimplemented!("new kind of change"),
+    ///     };
+    ///     log2.lock().unwrap().push(op);
+    /// });
+    ///
+    /// let mut guard = write.guard();
+    /// guard.insert(1, 10);
+    /// guard.insert(1, 20);
+    /// guard.insert(2, 30);
+    /// guard.remove(1);
+    /// guard.publish();
+    ///
+    /// assert_eq!(
+    ///     *log.lock().unwrap(),
+    ///     [
+    ///         MapOp::Insert(1, 10),
+    ///         MapOp::Replace(1, 20),
+    ///         MapOp::Insert(2, 30),
+    ///         MapOp::Remove(1),
+    ///     ]
+    /// );
+    ///
+    /// // The log can be replayed to rebuild the map
+    /// let (mut replica, replica_read) = flashmap::new::<u32, u32>();
+    /// replica.guard().apply(log.lock().unwrap().drain(..));
+    /// assert_eq!(replica_read.guard().len(), 1);
+    /// assert_eq!(replica_read.guard().get(&2), read.guard().get(&2));
+    /// ```
+    pub fn set_op_observer<F>(&mut self, observer: F)
+    where
+        F: Fn(&MapOp<&K, &V>) + Send + Sync + 'static,
+    {
+        self.observer = Some(Box::new(observer));
+    }
+
+    /// Drops values evicted by previous writes if no reader can still observe them, without
+    /// blocking. Returns whether the values were reclaimed.
+    ///
+    /// Normally, values which were removed or replaced are only dropped once the next write
+    /// guard is created, since that is when the writer waits for readers to move off of the
+    /// previous version of the map. If writes are infrequent, this may keep large values alive for
+    /// a long time. This method can be called periodically to reclaim them sooner. If some readers
+    /// are still viewing the previous version of the map, then this does nothing and returns
+    /// `false`. To have the values dropped automatically instead, see
+    /// [`Builder::eager_reclaim`](crate::Builder::eager_reclaim).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use std::sync::Arc;
+    ///
+    /// let (mut write, _read) = flashmap::new::<u32, Arc<()>>();
+    /// let value = Arc::new(());
+    ///
+    /// write.guard().insert(1, Arc::clone(&value));
+    /// write.guard().remove(1);
+    ///
+    /// // The writer still holds a copy of the removed value
+    /// assert_eq!(Arc::strong_count(&value), 2);
+    ///
+    /// // No reader is viewing the old map, so the value can be dropped
+    /// assert!(write.try_reclaim());
+    /// assert_eq!(Arc::strong_count(&value), 1);
+    /// ```
+    pub fn try_reclaim(&mut self) -> bool {
+        if !self.core.is_synchronized() {
+            return false;
+        }
+
+        self.flush_writer_map();
+        true
+    }
+
+    /// Brings the writer's copy of the map up to date with all published changes.
+    ///
+    /// This is normally done lazily when the next write guard is created. Flushing eagerly drops
+    /// any removed or replaced values which were not leaked, and means that the next call to
+    /// [`guard`](crate::WriteHandle::guard) has less work to do. Like `guard`, this blocks until
+    /// all readers have moved off of the previous version of the map. See
+    /// [`try_reclaim`](crate::WriteHandle::try_reclaim) for a non-blocking alternative.
+    ///
+    /// This does not publish anything: since changes are published whenever a write guard is
+    /// dropped, there is never anything left to publish outside of a guard.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use std::sync::Arc;
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, Arc<()>>();
+    /// let value = Arc::new(());
+    ///
+    /// write.guard().insert(1, Arc::clone(&value));
+    /// write.guard().remove(1);
+    /// assert_eq!(Arc::strong_count(&value), 2);
+    ///
+    /// write.flush();
+    /// assert_eq!(Arc::strong_count(&value), 1);
+    /// assert!(read.guard().is_empty());
+    /// ```
+    pub fn flush(&mut self) {
+        self.prepare_writer_map();
+    }
+
+    /// Replaces the entire contents of the map with the entries of the given map, and publishes
+    /// the change, so that new read guards observe either all of the old entries or all of the new
+    /// ones.
+    ///
+    /// This is cheaper than working out which entries changed when the new contents were built
+    /// from scratch, for instance when reloading a configuration. The old entries are dropped once
+    /// it is safe to do so, and the map is resized to fit the new entries. Like
+    /// [`guard`](crate::WriteHandle::guard), this may block while waiting for readers to move off
+    /// of the writer's map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// use std::collections::HashMap;
+    ///
+    /// let (mut write, read) = flashmap::new::<String, u32>();
+    /// write.guard().insert("timeout".to_owned(), 30);
+    ///
+    /// let mut reloaded = HashMap::new();
+    /// reloaded.insert("retries".to_owned(), 3);
+    /// reloaded.insert("backoff".to_owned(), 100);
+    /// write.replace_all(reloaded);
+    ///
+    /// let guard = read.guard();
+    /// assert_eq!(guard.len(), 2);
+    /// assert!(!guard.contains_key("timeout"));
+    /// assert_eq!(guard.get("retries"), Some(&3));
+    /// ```
+    pub fn replace_all<S2>(&mut self, new: HashMap<K, V, S2>) {
+        let map = self.prepare_writer_map();
+
+        // The guard publishes the change when dropped
+        WriteGuard {
+            map,
+            handle: self,
+            handle_uid: self.uid,
+            publish_on_drop: true,
+        }
+        .reset(new);
+    }
+
+    /// Removes every entry from the map, returning an iterator over the owned keys and values.
+    ///
+    /// The removal is published immediately, and this function then blocks until no readers can
+    /// observe the removed entries anymore, so that ownership of them can be handed out. Unlike
+    /// [`into_hash_map`](crate::WriteHandle::into_hash_map), the handle can still be used
+    /// afterwards, and readers see an empty map with the same capacity as before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    ///
+    /// let mut guard = write.guard();
+    /// guard.insert(1, "one".to_owned());
+    /// guard.insert(2, "two".to_owned());
+    /// guard.publish();
+    ///
+    /// let mut drained = write.drain().collect::<Vec<_>>();
+    /// drained.sort_unstable();
+    /// assert_eq!(drained, [(1, "one".to_owned()), (2, "two".to_owned())]);
+    ///
+    /// assert!(read.guard().is_empty());
+    /// write.guard().insert(3, "three".to_owned());
+    /// assert_eq!(read.guard().len(), 1);
+    /// ```
+    pub fn drain(&mut self) -> impl Iterator<Item = (K, V)> {
+        let entries = self.prepare_writer_map().with_mut(|map_ptr| {
+            let map = unsafe { &mut *map_ptr };
+            let capacity = map.capacity();
+            let entries = map.drain().collect::<Vec<_>>();
+
+            // The other map only forgets its copies of the entries, since ownership of them is
+            // taken below
+            let mut operation = Operation::new(RawOperation::Clear(capacity));
+            operation.make_leaky();
+            self.operations
+                .with_mut(|ops_ptr| unsafe { &mut *ops_ptr }.push(operation));
+
+            entries
+        });
+
+        unsafe { self.publish() };
+        self.synchronize();
+
+        // SAFETY: no readers can observe the drained entries after synchronizing, and the other
+        // map will not drop them
+        entries
+            .into_iter()
+            .map(|(key, value)| unsafe { (Alias::into_owned(key), Alias::into_owned(value)) })
+    }
+
+    /// Consumes this write handle and moves the map's contents into a standard `HashMap`, or
+    /// returns the handle back if any read handles (or owned read guards) are still alive.
+    ///
+    /// All pending changes are flushed first, so the returned map reflects every write made so
+    /// far. Since no readers remain, ownership of the keys and values can be taken without
+    /// cloning them. The entries are moved into a fresh allocation which uses a clone of the
+    /// map's hasher.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// write.guard().insert(1, "one".to_owned());
+    ///
+    /// // The reader still has access to the map
+    /// let mut write = write.into_hash_map().unwrap_err();
+    /// drop(read);
+    ///
+    /// let map = write.into_hash_map().ok().expect("no readers remain");
+    /// assert_eq!(map.len(), 1);
+    /// assert_eq!(map[&1], "one");
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn into_hash_map(mut self) -> Result<HashMap<K, V, S>, Self>
+    where
+        S: Clone,
+    {
+        // Uniqueness of the core proves that no readers remain, and synchronizes with their drop
+        if Arc::get_mut(&mut self.core).is_none() {
+            return Err(self);
+        }
+
+        let writer_map = self.prepare_writer_map();
+
+        // The published map shares its entries with the writer's map, so we empty it without
+        // dropping anything. Dropping aliases is a no-op.
+        self.core
+            .published_map()
+            .with_mut(|map_ptr| unsafe { &mut *map_ptr }.drain().for_each(drop));
+
+        let map =
+            writer_map.with_mut(|map_ptr| {
+                let map = unsafe { &mut *map_ptr };
+                let mut out = HashMap::with_capacity_and_hasher(map.len(), map.hasher().clone());
+
+                // SAFETY: there are no readers and the published map was emptied, so the writer's map
+                // holds the only remaining copy of each entry
+                out.extend(map.drain().map(|(key, value)| unsafe {
+                    (Alias::into_owned(key), Alias::into_owned(value))
+                }));
+                out
+            });
+
+        // Both maps and the operation log are empty, so dropping the handle frees nothing else
+        Ok(map)
+    }
+
+    /// Returns whether or not the most recent publish made any changes visible to readers.
+    ///
+    /// Dropping a write guard (or a [`BatchGuard`](crate::BatchGuard)) always publishes, but if no
+    /// changes were made through it, then readers are not moved over to a new version of the map,
+    /// and this returns `false`. This can be used to avoid notifying other parts of a program
+    /// about changes that never happened. If nothing was published yet, this returns `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use flashmap;
+    /// let (mut write, read) = flashmap::new::<u32, u32>();
+    /// assert!(!write.last_publish_changed());
+    ///
+    /// write.guard().insert(1, 2);
+    /// assert!(write.last_publish_changed());
+    ///
+    /// // Reading through a write guard does not change anything
+    /// assert_eq!(write.guard().get(&1), Some(&2));
+    /// assert!(!write.last_publish_changed());
+    /// ```
+    #[inline]
+    pub fn last_publish_changed(&self) -> bool {
+        self.last_publish_changed.get()
+    }
+
+    /// Returns whether or not any changes were made since the last publish. Every change is
+    /// recorded in the operation log, which is cleared once the writer's map is brought up to date
+    /// before creating a new guard, so a non-empty log means there is something to publish.
+    #[inline]
+    fn is_dirty(&self) -> bool {
+        self.operations
+            .with(|ops_ptr| !unsafe { &*ops_ptr }.is_empty())
+    }
+
+    /// Publishes the changes made since the last publish, if there are any. The caller must ensure
+    /// that this is only called once per set of changes.
+    #[inline]
+    unsafe fn publish(&self) {
+        let dirty = self.is_dirty();
+        self.last_publish_changed.set(dirty);
+        if !dirty {
+            return;
+        }
+
+        if let Some(observer) = &self.observer {
+            self.operations
+                .with(|ops_ptr| observer::notify(observer, unsafe { &*ops_ptr }));
+        }
+
+        if let (Some(tracker), Some(order)) = (&self.order, self.core.writer_insertion_order()) {
+            tracker.with_mut(|tracker_ptr| {
+                order.with_mut(|order_ptr| {
+                    self.operations.with(|ops_ptr| unsafe {
+                        (*tracker_ptr).record(&*ops_ptr, &mut *order_ptr)
+                    })
+                })
+            });
+        }
+
+        #[cfg(debug_assertions)]
+        self.debug_check_writer_map();
+
+        unsafe { self.record_versions() };
+        unsafe { self.core.advance_snapshot_version() };
+        let evicted = if self.core.reclaims_eagerly() {
+            unsafe { self.take_evicted() }
+        } else {
+            Vec::new()
+        };
+        unsafe { self.core.publish(evicted) };
+    }
+
+    /// Checks that the length of the writer's map is consistent with the published map and the
+    /// changes made since it was published. This catches bugs which would make readers observe a
+    /// map that is missing changes, which would otherwise only show up as stale reads.
+    #[cfg(debug_assertions)]
+    fn debug_check_writer_map(&self) {
+        // Guards publish when dropped during unwinding, in which case panicking again would abort
+        if std::thread::panicking() {
+            return;
+        }
+
+        let published_len = self
+            .core
+            .published_map()
+            .with(|map_ptr| unsafe { &*map_ptr }.len());
+        let expected_len = self.operations.with(|ops_ptr| {
+            unsafe { &*ops_ptr }
+                .iter()
+                .try_fold(published_len, |len, operation| match &operation.raw {
+                    RawOperation::InsertUnique(..) => Some(len + 1),
+                    RawOperation::Remove(_) | RawOperation::RemoveAliased(_) => len.checked_sub(1),
+                    RawOperation::Clear(_) => Some(0),
+                    RawOperation::Reset(entries) => Some(entries.len()),
+                    RawOperation::Replace(..)
+                    | RawOperation::ReplaceAliased(..)
+                    | RawOperation::Drop(_) => Some(len),
+                })
+        });
+        let writer_len = self
+            .core
+            .writer_map()
+            .with(|map_ptr| unsafe { &*map_ptr }.len());
+
+        match expected_len {
+            Some(expected_len) if expected_len == writer_len => {}
+            Some(expected_len) => panic!(
+                "Writer map is out of sync with the operation log: expected {expected_len} \
+                 entries, found {writer_len}"
+            ),
+            None => panic!(
+                "Writer map is out of sync with the operation log: the log removes more \
+                 entries than the map contains, found {writer_len}"
+            ),
+        }
+    }
+
+    /// Assigns new versions to the entries changed by operations which were not yet recorded. The
+    /// caller must ensure that no readers are viewing the writer's map.
+    #[inline]
+    unsafe fn record_versions(&self) {
+        if let (Some(tracker), Some(versions)) = (&self.versions, self.core.writer_entry_versions())
+        {
+            tracker.with_mut(|tracker_ptr| {
+                versions.with_mut(|versions_ptr| {
+                    self.operations.with(|ops_ptr| unsafe {
+                        (*tracker_ptr).record(&*ops_ptr, &mut *versions_ptr)
+                    })
+                })
+            });
+        }
+    }
+
+    /// Collects copies of the values which replaying the operation log onto the published map
+    /// would drop, and marks the operations dropping them as leaky, so that they can be dropped
+    /// by readers instead. The caller must ensure that the log has not been replayed yet.
+    unsafe fn take_evicted(&self) -> Vec<Alias<V>> {
+        let published = self
+            .core
+            .published_map()
+            .with(|map_ptr| unsafe { &*map_ptr });
+
+        self.operations.with_mut(|ops_ptr| {
+            let operations = unsafe { &mut *ops_ptr };
+            let mut evicted = Vec::new();
+
+            // The value of every key changed by the operations replayed so far, or `None` if the
+            // key was removed. Any other key still has its value from the published map, unless
+            // the map was cleared.
+            let mut latest = HashMap::<&Alias<K>, Option<&Alias<V>>>::new();
+            let mut cleared = false;
+
+            for operation in operations.iter() {
+                let (key, value) = match &operation.raw {
+                    RawOperation::InsertUnique(key, value) => {
+                        latest.insert(key, Some(value));
+                        continue;
+                    }
+                    RawOperation::Replace(key, value)
+                    | RawOperation::ReplaceAliased(key, value) => (key, Some(value)),
+                    RawOperation::Remove(key) | RawOperation::RemoveAliased(key) => (key, None),
+                    RawOperation::Drop(value) => {
+                        evicted.push(unsafe { Alias::copy(value) });
+                        continue;
+                    }
+                    // Clearing also drops keys, so these values are still dropped when replaying
+                    RawOperation::Clear(_) => {
+                        latest.clear();
+                        cleared = true;
+                        continue;
+                    }
+                    RawOperation::Reset(entries) => {
+                        latest.clear();
+                        cleared = true;
+                        for (key, value) in entries {
+                            latest.insert(key, Some(value));
+                        }
+                        continue;
+                    }
+                };
+
+                // Values which can still be read through an `Evicted` are dropped by the writer
+                // once it flushes the log, which cannot happen before the `Evicted` is dropped
+                if !operation.leaky && !operation.is_borrowed() {
+                    let previous = match latest.get(key) {
+                        Some(previous) => *previous,
+                        None if cleared => None,
+                        None => published.get(key),
+                    };
+                    let previous =
+                        previous.expect("Operation log changes a key which is not in the map");
+                    evicted.push(unsafe { Alias::copy(previous) });
+                }
+                latest.insert(key, value);
+            }
+
+            for operation in operations.iter_mut() {
+                if operation.is_borrowed() {
+                    continue;
+                }
+
+                if let RawOperation::Replace(..)
+                | RawOperation::ReplaceAliased(..)
+                | RawOperation::Remove(_)
+                | RawOperation::RemoveAliased(_)
+                | RawOperation::Drop(_) = operation.raw
+                {
+                    operation.make_leaky();
+                }
+            }
+
+            evicted
+        })
+    }
+
+    /// Returns the current version of the given key as seen by the writer. The caller must ensure
+    /// that no readers are viewing the writer's map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map is not versioned.
+    #[inline]
+    unsafe fn writer_version<Q>(&self, key: &Q) -> Option<u64>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let versions = self
+            .core
+            .writer_entry_versions()
+            .expect("Map was not built with Builder::versioned");
+        unsafe { self.record_versions() };
+        versions.with(|versions_ptr| {
+            unsafe { &*versions_ptr }
+                .get(BorrowHelper::new_ref(key))
+                .copied()
+        })
+    }
+
+    /// Waits for the writer's map to become available and brings it up to date, returning it.
+    #[inline]
+    fn prepare_writer_map(&self) -> &UnsafeCell<Map<K, V, S>> {
         self.synchronize();
+        self.flush_writer_map()
+    }
+
+    /// Brings the writer's map up to date, returning it. The caller must ensure that no readers
+    /// are viewing the writer's map.
+    #[inline]
+    fn flush_writer_map(&self) -> &UnsafeCell<Map<K, V, S>> {
+        unsafe { self.core.reclaim_evicted() };
+
+        // The recorded changes refer to keys which may be dropped when flushing the operations
+        if let (Some(tracker), Some(versions)) = (&self.versions, self.core.writer_entry_versions())
+        {
+            tracker.with_mut(|tracker_ptr| {
+                versions
+                    .with_mut(|versions_ptr| unsafe { (*tracker_ptr).replay(&mut *versions_ptr) })
+            });
+        }
+
         let map = self.core.writer_map();
         map.with_mut(|map_ptr| {
             self.operations.with_mut(|ops_ptr| {
@@ -135,11 +1101,13 @@ where
             });
         });
 
-        View::new(WriteGuard {
-            map,
-            handle: self,
-            handle_uid: self.uid,
-        })
+        if let (Some(tracker), Some(order)) = (&self.order, self.core.writer_insertion_order()) {
+            tracker.with_mut(|tracker_ptr| {
+                order.with_mut(|order_ptr| unsafe { (*tracker_ptr).replay(&mut *order_ptr) })
+            });
+        }
+
+        map
     }
 
     /// Reclaims a leaked value, providing ownership of the underlying value.
@@ -172,6 +1140,45 @@ where
         (self.reclaimer())(leaked)
     }
 
+    /// Reclaims a leaked value without checking that it came from this map, providing ownership
+    /// of the underlying value.
+    ///
+    /// This still waits for readers to move off of the writer's map, exactly like
+    /// [`reclaim_one`](crate::WriteHandle::reclaim_one), so it is only beneficial in tight loops
+    /// where the check itself is measurable. Prefer [`reclaimer`](crate::WriteHandle::reclaimer)
+    /// or [`reclaim_all`](crate::WriteHandle::reclaim_all) when reclaiming many values, since they
+    /// only synchronize once.
+    ///
+    /// # Safety
+    ///
+    /// `leaked` must have been leaked from a value evicted from the map this handle is associated
+    /// with. Otherwise, readers of the map it actually came from may still be accessing the value,
+    /// so taking ownership of it is undefined behavior.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::{self, Evicted};
+    ///
+    /// let (mut write, read) = flashmap::new::<String, String>();
+    ///
+    /// write.guard().insert("ferris".to_owned(), "crab".to_owned());
+    ///
+    /// let leaked = write.guard().remove("ferris".to_owned())
+    ///     .map(Evicted::leak)
+    ///     .unwrap();
+    ///
+    /// // Safety: the value was leaked from this map
+    /// let value = unsafe { write.reclaim_one_unchecked(leaked) };
+    /// assert_eq!(value, "crab");
+    /// ```
+    #[inline]
+    pub unsafe fn reclaim_one_unchecked(&self, leaked: Leaked<V>) -> V {
+        debug_assert!(self.uid == leaked.handle_uid, "{LEAKED_VALUE_MISMATCH}");
+        self.synchronize();
+        unsafe { Alias::into_owned(leaked.value) }
+    }
+
     /// Returns a function which can safely reclaim leaked values. This is useful for reclaiming
     /// multiple leaked values while only performign the necessary synchronization once.
     ///
@@ -217,39 +1224,130 @@ where
         }
     }
 
+    /// Reclaims a collection of leaked values, providing ownership of the underlying values in the
+    /// same order. The necessary synchronization is only performed once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the leaked values provided came from a different map than the one this
+    /// handle is associated with. This check is performed before any values are reclaimed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::{self, Evicted};
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    ///
+    /// let mut guard = write.guard();
+    /// guard.insert(1, "one".to_owned());
+    /// guard.insert(2, "two".to_owned());
+    /// guard.publish();
+    ///
+    /// let mut guard = write.guard();
+    /// let leaked = [1, 2]
+    ///     .into_iter()
+    ///     .filter_map(|key| guard.remove(key).map(Evicted::leak))
+    ///     .collect::<Vec<_>>();
+    /// guard.publish();
+    ///
+    /// assert_eq!(write.reclaim_all(leaked), ["one", "two"]);
+    /// ```
+    pub fn reclaim_all(&self, leaked: Vec<Leaked<V>>) -> Vec<V> {
+        assert!(
+            leaked.iter().all(|leaked| leaked.handle_uid == self.uid),
+            "{LEAKED_VALUE_MISMATCH}"
+        );
+        self.synchronize();
+        leaked
+            .into_iter()
+            .map(|leaked| unsafe { Alias::into_owned(leaked.value) })
+            .collect()
+    }
+
     #[inline]
-    unsafe fn flush_operations(operations: &mut Vec<Operation<K, V>>, map: &mut Map<K, V, S>) {
+    unsafe fn flush_operations(operations: &mut OperationLog<K, V>, map: &mut Map<K, V, S>) {
         // We do unchecked ops in here since this function benches pretty hot when doing a lot
         // of writing
 
+        // Bulk loads consist entirely of inserts, in which case we can size the map once up front
+        // rather than growing it repeatedly, and skip dispatching on every operation
+        if operations.is_insert_only() {
+            map.reserve(operations.len());
+            for operation in operations.drain() {
+                let RawOperation::InsertUnique(key, value) = operation.raw else {
+                    // Safety: the log only holds inserts, as checked above
+                    unsafe { hint::unreachable_unchecked() }
+                };
+                map.insert_unique_unchecked(key, value);
+            }
+            return;
+        }
+
         for Operation {
             raw: mut operation,
             leaky,
-        } in operations.drain(..)
+            ..
+        } in operations.drain()
         {
             match operation {
                 RawOperation::InsertUnique(key, value) => {
                     map.insert_unique_unchecked(key, value);
                 }
-                RawOperation::Replace(ref key, value) => {
-                    let slot =
-                        unsafe { map.get_mut(BorrowHelper::new_ref(key)).unwrap_unchecked() };
+                RawOperation::Replace(mut key, value) => {
+                    let slot = unsafe { map.get_mut(&key).unwrap_unchecked() };
                     if !leaky {
                         unsafe { Alias::drop(slot) };
                     }
                     *slot = value;
+                    unsafe { Alias::drop(&mut key) };
                 }
-                RawOperation::Remove(ref key) => {
-                    let (mut k, mut v) = unsafe {
-                        map.remove_entry(BorrowHelper::new_ref(key))
-                            .unwrap_unchecked()
-                    };
+                RawOperation::ReplaceAliased(ref key, value) => {
+                    let slot = unsafe { map.get_mut(key).unwrap_unchecked() };
+                    if !leaky {
+                        unsafe { Alias::drop(slot) };
+                    }
+                    *slot = value;
+                }
+                RawOperation::Remove(mut key) => {
+                    let (mut k, mut v) = unsafe { map.remove_entry(&key).unwrap_unchecked() };
                     unsafe { Alias::drop(&mut k) };
                     if !leaky {
                         unsafe { Alias::drop(&mut v) };
                     }
+                    unsafe { Alias::drop(&mut key) };
+                }
+                RawOperation::RemoveAliased(ref key) => {
+                    let (mut k, mut v) = unsafe { map.remove_entry(key).unwrap_unchecked() };
+                    unsafe { Alias::drop(&mut k) };
+                    if !leaky {
+                        unsafe { Alias::drop(&mut v) };
+                    }
+                }
+                RawOperation::Drop(ref mut value) => {
+                    if !leaky {
+                        unsafe { Alias::drop(value) };
+                    }
+                }
+                RawOperation::Clear(capacity) => {
+                    map.drain().for_each(|(mut key, mut value)| {
+                        if !leaky {
+                            unsafe { Alias::drop(&mut key) };
+                            unsafe { Alias::drop(&mut value) };
+                        }
+                    });
+                    WriteGuard::resize_empty(map, capacity);
+                }
+                RawOperation::Reset(entries) => {
+                    map.drain().for_each(|(mut key, mut value)| {
+                        unsafe { Alias::drop(&mut key) };
+                        unsafe { Alias::drop(&mut value) };
+                    });
+                    WriteGuard::resize_empty(map, entries.len());
+                    for (key, value) in entries {
+                        map.insert_unique_unchecked(key, value);
+                    }
                 }
-                RawOperation::Drop(ref mut value) => unsafe { Alias::drop(value) },
             }
         }
     }
@@ -262,6 +1360,7 @@ where
 {
     fn drop(&mut self) {
         self.synchronize();
+        unsafe { self.core.reclaim_evicted() };
         let map = self.core.writer_map();
         map.with_mut(|map_ptr| {
             self.operations.with_mut(|ops_ptr| unsafe {
@@ -271,6 +1370,26 @@ where
     }
 }
 
+/// Provides immutable access to the map as seen by the writer.
+///
+/// See [`WriteHandle::peek`](crate::WriteHandle::peek) for examples. See [`View`](crate::View)
+/// for the public API to interact with the underlying map.
+pub struct PeekGuard<'guard, K, V, S = RandomState> {
+    map: &'guard UnsafeCell<Map<K, V, S>>,
+}
+
+impl<'guard, K, V, S> ReadAccess for PeekGuard<'guard, K, V, S> {
+    type Map = Map<K, V, S>;
+
+    #[inline]
+    fn with_map<'read, F, R>(&'read self, op: F) -> R
+    where
+        F: FnOnce(&'read Self::Map) -> R,
+    {
+        self.map.with(|map_ptr| op(unsafe { &*map_ptr }))
+    }
+}
+
 /// Provides mutable access to the underlying map, and publishes all changes to new readers when
 /// dropped.
 ///
@@ -280,6 +1399,7 @@ pub struct WriteGuard<'guard, K: Eq + Hash, V, S: BuildHasher = RandomState> {
     map: &'guard UnsafeCell<Map<K, V, S>>,
     handle: &'guard WriteHandle<K, V, S>,
     handle_uid: WriterUid,
+    publish_on_drop: bool,
 }
 
 impl<'guard, K, V, S> ReadAccess for WriteGuard<'guard, K, V, S>
@@ -306,7 +1426,7 @@ where
     #[inline]
     fn with_map_mut<'write, F, R>(&'write mut self, op: F) -> R
     where
-        F: FnOnce(&'write mut Map<K, V, S>, &'write mut Vec<Operation<K, V>>) -> R,
+        F: FnOnce(&'write mut Map<K, V, S>, &'write mut OperationLog<K, V>) -> R,
     {
         self.map.with_mut(|map_ptr| {
             self.handle
@@ -320,25 +1440,79 @@ where
     where
         'guard: 'ret,
     {
+        let (_, evicted) = self.insert_raw(key, value);
+        evicted.map(|alias| unsafe { Evicted::new(self, alias) })
+    }
+
+    #[inline]
+    pub(crate) fn insert_and_get<'ret>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> (&V, Option<Evicted<'ret, K, V>>)
+    where
+        'guard: 'ret,
+    {
+        let (inserted, evicted) = self.insert_raw(key, value);
+        let evicted = evicted.map(|alias| unsafe { Evicted::new(self, alias) });
+        // Safety: the value lives in the writer's map, which cannot be modified while the
+        // returned reference borrows this guard. Creating the evicted value only reads the
+        // length of the operation log.
+        (unsafe { &*inserted }, evicted)
+    }
+
+    #[inline]
+    pub(crate) fn set(&mut self, key: K, value: V) {
+        // The evicted value is dropped when the operation is replayed on the other map
+        let _ = self.insert_raw(key, value);
+    }
+
+    #[inline]
+    pub(crate) unsafe fn insert_unique_unchecked(&mut self, key: K, value: V) {
+        let key = Alias::new(key);
         let value = Alias::new(value);
 
-        let evicted = self.with_map_mut(|map, operations| {
+        self.with_map_mut(|map, operations| {
+            map.insert_unique_unchecked(unsafe { Alias::copy(&key) }, unsafe {
+                Alias::copy(&value)
+            });
+            operations.push(Operation::new(RawOperation::InsertUnique(key, value)));
+        });
+    }
+
+    #[inline]
+    pub(crate) fn reserve_ops(&mut self, additional: usize) {
+        self.with_map_mut(|_, operations| operations.reserve(additional));
+    }
+
+    /// Inserts the given entry, returning a pointer to the value now in the writer's map along
+    /// with the evicted value if the key was already present. The evicted value is still in use by
+    /// the other map.
+    #[inline]
+    fn insert_raw(&mut self, key: K, value: V) -> (*const V, Option<Alias<V>>) {
+        let value = Alias::new(value);
+
+        self.with_map_mut(|map, operations| {
             match map.raw_entry_mut().from_key(BorrowHelper::new_ref(&key)) {
-                RawEntryMut::Vacant(entry) => {
+                hash_map::RawEntryMut::Vacant(entry) => {
                     let key = Alias::new(key);
-                    entry.insert(unsafe { Alias::copy(&key) }, unsafe { Alias::copy(&value) });
+                    let (_, slot) =
+                        entry.insert(unsafe { Alias::copy(&key) }, unsafe { Alias::copy(&value) });
+                    let inserted: *const V = &**slot;
                     operations.push(Operation::new(RawOperation::InsertUnique(key, value)));
-                    None
+                    (inserted, None)
                 }
-                RawEntryMut::Occupied(mut entry) => {
+                hash_map::RawEntryMut::Occupied(mut entry) => {
                     let old = mem::replace(entry.get_mut(), unsafe { Alias::copy(&value) });
-                    operations.push(Operation::new(RawOperation::Replace(key, value)));
-                    Some(old)
+                    let inserted: *const V = &**entry.get();
+                    operations.push(Operation::new(RawOperation::Replace(
+                        Alias::new(key),
+                        value,
+                    )));
+                    (inserted, Some(old))
                 }
             }
-        });
-
-        evicted.map(|alias| unsafe { Evicted::new(self, alias) })
+        })
     }
 
     #[inline]
@@ -346,23 +1520,63 @@ where
     where
         F: FnOnce(&V) -> V,
         'guard: 'ret,
+    {
+        match self.try_replace(key, |value| Ok::<_, Infallible>(op(value))) {
+            Ok(evicted) => evicted,
+            Err(never) => match never {},
+        }
+    }
+
+    #[inline]
+    pub(crate) fn try_replace<'ret, F, E>(
+        &mut self,
+        key: K,
+        op: F,
+    ) -> Result<Option<Evicted<'ret, K, V>>, E>
+    where
+        F: FnOnce(&V) -> Result<V, E>,
+        'guard: 'ret,
     {
         let evicted =
             self.with_map_mut(
                 |map, operations| match map.get_mut(BorrowHelper::new_ref(&key)) {
                     Some(value) => {
-                        let new_value = Alias::new(op(&**value));
-                        operations.push(Operation::new(RawOperation::Replace(key, unsafe {
-                            Alias::copy(&new_value)
-                        })));
+                        // The closure must be called before anything is recorded, so that if it
+                        // panics or fails the map and operation log are left untouched
+                        let new_value = Alias::new(op(&**value)?);
+                        operations.push(Operation::new(RawOperation::Replace(
+                            Alias::new(key),
+                            unsafe { Alias::copy(&new_value) },
+                        )));
                         let old_value = mem::replace(value, new_value);
-                        Some(old_value)
+                        Ok(Some(old_value))
                     }
-                    None => None,
+                    None => Ok(None),
                 },
-            );
+            )?;
 
-        evicted.map(|value| unsafe { Evicted::new(self, value) })
+        Ok(evicted.map(|value| unsafe { Evicted::new(self, value) }))
+    }
+
+    #[inline]
+    pub(crate) fn replace_if_version<'ret>(
+        &mut self,
+        key: K,
+        expected: u64,
+        value: V,
+    ) -> Result<Evicted<'ret, K, V>, VersionMismatch>
+    where
+        'guard: 'ret,
+    {
+        // Safety: readers never view the map of a live write guard
+        let current = unsafe { self.handle.writer_version(&key) };
+        if current != Some(expected) {
+            return Err(VersionMismatch::new(current));
+        }
+
+        Ok(self
+            .insert(key, value)
+            .expect("Key disappeared after its version was checked"))
     }
 
     #[inline]
@@ -374,7 +1588,7 @@ where
             let removed = map.remove(BorrowHelper::new_ref(&key));
 
             if removed.is_some() {
-                operations.push(Operation::new(RawOperation::Remove(key)));
+                operations.push(Operation::new(RawOperation::Remove(Alias::new(key))));
             }
 
             removed
@@ -383,6 +1597,237 @@ where
         evicted.map(|value| unsafe { Evicted::new(self, value) })
     }
 
+    #[inline]
+    pub(crate) fn map_values<F>(&mut self, mut op: F)
+    where
+        F: FnMut(&K, &V) -> V,
+    {
+        self.with_map_mut(|map, operations| {
+            operations.reserve(map.len());
+
+            for (key, value) in map.iter_mut() {
+                let new_value = Alias::new(op(key, value));
+                operations.push(Operation::new(RawOperation::ReplaceAliased(
+                    unsafe { Alias::copy(key) },
+                    unsafe { Alias::copy(&new_value) },
+                )));
+                // The old value is dropped when this operation is replayed on the other map
+                *value = new_value;
+            }
+        });
+    }
+
+    #[inline]
+    pub(crate) fn retain_map<F>(&mut self, mut op: F)
+    where
+        F: FnMut(&K, &V) -> Option<V>,
+    {
+        self.with_map_mut(|map, operations| {
+            map.retain(|key, value| match op(key, value) {
+                Some(new_value) => {
+                    let new_value = Alias::new(new_value);
+                    operations.push(Operation::new(RawOperation::ReplaceAliased(
+                        unsafe { Alias::copy(key) },
+                        unsafe { Alias::copy(&new_value) },
+                    )));
+                    // The old value is dropped when this operation is replayed on the other map
+                    *value = new_value;
+                    true
+                }
+                None => {
+                    // The entry is dropped when this operation is replayed on the other map
+                    operations.push(Operation::new(RawOperation::RemoveAliased(unsafe {
+                        Alias::copy(key)
+                    })));
+                    false
+                }
+            });
+        });
+    }
+
+    #[inline]
+    pub(crate) fn clear_and_shrink(&mut self, capacity: usize) {
+        self.with_map_mut(|map, operations| {
+            // The entries are dropped when this operation is replayed on the other map
+            map.drain().for_each(drop);
+            Self::resize_empty(map, capacity);
+            operations.push(Operation::new(RawOperation::Clear(capacity)));
+        });
+    }
+
+    /// Replaces every entry in the map with the given entries. The new entries are inserted into
+    /// a second table as well, which replaces the table of the other map wholesale once this
+    /// operation is replayed, rather than inserting every entry again.
+    #[inline]
+    pub(crate) fn reset<S2>(&mut self, entries: HashMap<K, V, S2>) {
+        // Hashing runs user code which may panic, so every entry is hashed before anything is
+        // changed. That way a panic leaves the map and operation log untouched, and the entries
+        // are dropped normally.
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        let hashes = self.with_map_mut(|map, _| {
+            entries
+                .iter()
+                .map(|(key, _)| map.hasher().hash_one(key))
+                .collect::<Vec<_>>()
+        });
+
+        self.with_map_mut(|map, operations| {
+            // The entries are dropped when this operation is replayed on the other map
+            map.drain().for_each(drop);
+            Self::resize_empty(map, entries.len());
+
+            let mut originals = Vec::with_capacity(entries.len());
+            for ((key, value), hash) in entries.into_iter().zip(hashes) {
+                let (key, value) = (Alias::new(key), Alias::new(value));
+
+                // Safety: the table has room for every entry, and since the entries came from a
+                // map, there are no duplicate keys
+                unsafe {
+                    map.raw_table()
+                        .insert_no_grow(hash, (Alias::copy(&key), Alias::copy(&value)));
+                }
+                originals.push((key, value));
+            }
+
+            operations.push(Operation::new(RawOperation::Reset(originals)));
+        });
+    }
+
+    #[inline]
+    fn resize_empty(map: &mut Map<K, V, S>, capacity: usize) {
+        debug_assert!(map.is_empty());
+        map.shrink_to(capacity);
+        map.reserve(capacity);
+    }
+
+    #[inline]
+    pub(crate) fn extract_if<'ret, F>(&mut self, mut pred: F) -> Vec<(K, Evicted<'ret, K, V>)>
+    where
+        F: FnMut(&K, &V) -> bool,
+        K: Clone,
+        'guard: 'ret,
+    {
+        let extracted = self.with_map_mut(|map, operations| {
+            map.drain_filter(|key, value| pred(key, value))
+                .map(|(key, value)| {
+                    let owned_key = K::clone(&key);
+                    operations.push(Operation::new(RawOperation::RemoveAliased(key)));
+                    (owned_key, value, operations.len() - 1)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        extracted
+            .into_iter()
+            .map(|(key, value, operation)| {
+                (key, unsafe {
+                    Evicted::with_operation(
+                        &self.handle.operations,
+                        self.handle_uid,
+                        value,
+                        operation,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    #[inline]
+    pub(crate) fn get_mut<Q>(&mut self, key: &Q) -> Option<ValueMut<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.with_map_mut(|map, operations| {
+            match map.raw_entry_mut().from_key(BorrowHelper::new_ref(key)) {
+                hash_map::RawEntryMut::Occupied(entry) => {
+                    let (key, slot) = entry.into_key_value();
+                    Some(ValueMut {
+                        value: Some(V::clone(slot)),
+                        key,
+                        slot,
+                        operations,
+                    })
+                }
+                hash_map::RawEntryMut::Vacant(_) => None,
+            }
+        })
+    }
+
+    #[inline]
+    pub(crate) fn get_or_insert_with_key<F>(&mut self, key: K, op: F) -> &V
+    where
+        F: FnOnce(&K) -> V,
+    {
+        match self.get_or_try_insert_with_key(key, |key| Ok::<_, Infallible>(op(key))) {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_or_try_insert_with_key<F, E>(&mut self, key: K, op: F) -> Result<&V, E>
+    where
+        F: FnOnce(&K) -> Result<V, E>,
+    {
+        self.with_map_mut(|map, operations| {
+            match map.raw_entry_mut().from_key(BorrowHelper::new_ref(&key)) {
+                hash_map::RawEntryMut::Occupied(entry) => Ok(&**entry.into_mut()),
+                hash_map::RawEntryMut::Vacant(entry) => {
+                    // The closure must be called before anything is recorded, so that if it
+                    // panics or fails the map and operation log are left untouched
+                    let value = Alias::new(op(&key)?);
+                    let key = Alias::new(key);
+                    let (_, slot) =
+                        entry.insert(unsafe { Alias::copy(&key) }, unsafe { Alias::copy(&value) });
+                    operations.push(Operation::new(RawOperation::InsertUnique(key, value)));
+                    Ok(&**slot)
+                }
+            }
+        })
+    }
+
+    #[inline]
+    pub(crate) fn raw_entry_mut(&mut self) -> RawEntryBuilderMut<'_, K, V, S> {
+        let map = self.map.with_mut(|map_ptr| unsafe { &mut *map_ptr });
+        RawEntryBuilderMut::new(
+            map.raw_entry_mut(),
+            &self.handle.operations,
+            self.handle_uid,
+        )
+    }
+
+    #[inline]
+    pub(crate) fn entry_ref<'q, Q>(&mut self, key: &'q Q) -> EntryRef<'_, 'q, K, Q, V, S>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        let map = self.map.with_mut(|map_ptr| unsafe { &mut *map_ptr });
+        let hash = map.hasher().hash_one(key);
+        let present = map
+            .raw_entry()
+            .from_key_hashed_nocheck(hash, BorrowHelper::new_ref(key))
+            .is_some();
+
+        if !present {
+            return EntryRef::Vacant(VacantEntryRef::new(map, &self.handle.operations, key));
+        }
+
+        let builder = RawEntryBuilderMut::new(
+            map.raw_entry_mut(),
+            &self.handle.operations,
+            self.handle_uid,
+        );
+
+        // Safety: the hash was computed with the map's hasher
+        match unsafe { builder.from_key_hashed_nocheck(hash, key) } {
+            RawEntryMut::Occupied(entry) => EntryRef::Occupied(entry),
+            RawEntryMut::Vacant(_) => unreachable!("Entry disappeared between lookups"),
+        }
+    }
+
     #[inline]
     pub(crate) fn drop_lazily(&self, leaked: Leaked<V>) {
         assert!(
@@ -401,6 +1846,15 @@ where
         // publishing logic happens on drop
         drop(self);
     }
+
+    #[inline]
+    pub(crate) fn publish_and_synchronize(mut self) {
+        if self.publish_on_drop {
+            self.publish_on_drop = false;
+            unsafe { self.handle.publish() };
+            self.handle.core.synchronize();
+        }
+    }
 }
 
 impl<'guard, K, V, S> Drop for WriteGuard<'guard, K, V, S>
@@ -409,32 +1863,243 @@ where
     S: BuildHasher,
 {
     fn drop(&mut self) {
-        unsafe { self.handle.core.publish() };
+        if self.publish_on_drop {
+            unsafe { self.handle.publish() };
+        }
+    }
+}
+
+/// Allows for changes from multiple write guards to be published all at once, and publishes them
+/// when dropped.
+///
+/// See [`WriteHandle::batch`](crate::WriteHandle::batch) for details and examples.
+pub struct BatchGuard<'batch, K: Eq + Hash, V, S: BuildHasher = RandomState> {
+    map: &'batch UnsafeCell<Map<K, V, S>>,
+    handle: &'batch WriteHandle<K, V, S>,
+}
+
+impl<'batch, K, V, S> BatchGuard<'batch, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Creates a new [`WriteGuard`](crate::WriteGuard) wrapped in a [`View`](crate::View). The
+    /// returned guard behaves exactly like one returned by
+    /// [`WriteHandle::guard`](crate::WriteHandle::guard), except that dropping or publishing it
+    /// has no effect on readers. All changes are published when this batch guard is dropped.
+    ///
+    /// Creating a guard this way is cheap, since there is no need to wait for readers.
+    #[inline]
+    pub fn guard(&mut self) -> View<WriteGuard<'_, K, V, S>> {
+        View::new(WriteGuard {
+            map: self.map,
+            handle: self.handle,
+            handle_uid: self.handle.uid,
+            publish_on_drop: false,
+        })
+    }
+
+    /// Consumes this batch guard, publishing all changes made through it.
+    ///
+    /// This has the same effect as dropping the batch guard.
+    #[inline]
+    pub fn publish(self) {
+        // publishing logic happens on drop
+        drop(self);
+    }
+}
+
+impl<'batch, K, V, S> Drop for BatchGuard<'batch, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn drop(&mut self) {
+        unsafe { self.handle.publish() };
+    }
+}
+
+/// A mutable proxy for a value in the map, created via
+/// [`View::get_mut`](crate::View::get_mut).
+///
+/// This type dereferences to a **clone** of the value in the map, not the value itself, since the
+/// original may still be visible to readers. When the proxy is dropped, the clone replaces the
+/// value in the map, and the old value is dropped once it is safe to do so. The replacement is
+/// recorded even if the clone was never modified.
+pub struct ValueMut<'a, K, V> {
+    value: Option<V>,
+    key: &'a Alias<K>,
+    slot: &'a mut Alias<V>,
+    operations: &'a mut OperationLog<K, V>,
+}
+
+impl<K, V> Deref for ValueMut<'_, K, V> {
+    type Target = V;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // The value is only taken when the proxy is dropped
+        unsafe { self.value.as_ref().unwrap_unchecked() }
+    }
+}
+
+impl<K, V> DerefMut for ValueMut<'_, K, V> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { self.value.as_mut().unwrap_unchecked() }
+    }
+}
+
+impl<K, V> Drop for ValueMut<'_, K, V> {
+    fn drop(&mut self) {
+        let value = Alias::new(unsafe { self.value.take().unwrap_unchecked() });
+        self.operations
+            .push(Operation::new(RawOperation::ReplaceAliased(
+                unsafe { Alias::copy(self.key) },
+                unsafe { Alias::copy(&value) },
+            )));
+        // The old value is dropped when this operation is replayed on the other map
+        *self.slot = value;
+    }
+}
+
+/// A single change to a map, which can be applied later via
+/// [`View::apply`](crate::View::apply).
+///
+/// This is useful for replaying changes which were recorded elsewhere, such as in a write-ahead
+/// log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MapOp<K, V> {
+    /// Inserts the key-value pair, replacing the previous value if the key was present. See
+    /// [`View::insert`](crate::View::insert).
+    Insert(K, V),
+    /// Replaces the value of the key if it is present, and does nothing otherwise.
+    Replace(K, V),
+    /// Removes the key if it is present. See [`View::remove`](crate::View::remove).
+    Remove(K),
+    /// Removes every entry. See [`View::clear_and_shrink`](crate::View::clear_and_shrink).
+    Clear,
+}
+
+/// The changes made to the writer's map which were not yet applied to the other map.
+struct OperationLog<K, V> {
+    operations: Vec<Operation<K, V>>,
+    // Whether every operation in the log is an insertion of a new key, in which case replaying the
+    // log can skip looking up existing entries
+    insert_only: bool,
+    // Whether operations keep track of whether their `Evicted` is alive, which is only needed when
+    // publishing hands evicted values to readers
+    tracks_evicted: bool,
+}
+
+impl<K, V> OperationLog<K, V> {
+    #[inline]
+    fn new(tracks_evicted: bool) -> Self {
+        Self {
+            operations: Vec::new(),
+            insert_only: true,
+            tracks_evicted,
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, operation: Operation<K, V>) {
+        self.insert_only &= matches!(operation.raw, RawOperation::InsertUnique(..));
+        self.operations.push(operation);
+    }
+
+    #[inline]
+    fn is_insert_only(&self) -> bool {
+        self.insert_only
+    }
+
+    #[inline]
+    fn drain(&mut self) -> std::vec::Drain<'_, Operation<K, V>> {
+        self.insert_only = true;
+        self.operations.drain(..)
+    }
+
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        self.operations.reserve(additional);
+    }
+
+    #[inline]
+    fn shrink_to(&mut self, min_capacity: usize) {
+        self.operations.shrink_to(min_capacity);
+    }
+}
+
+impl<K, V> Deref for OperationLog<K, V> {
+    type Target = [Operation<K, V>];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.operations
+    }
+}
+
+impl<K, V> DerefMut for OperationLog<K, V> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.operations
     }
 }
 
 struct Operation<K, V> {
     raw: RawOperation<K, V>,
     leaky: bool,
+    // Shared with the `Evicted` handed out for this operation if the map reclaims eagerly, so that
+    // publishing can tell whether the evicted value may still be read through it. This is an `Rc`
+    // since an `Evicted` cannot leave the writer's thread, and cannot outlive the write handle.
+    evicted: Option<Rc<()>>,
 }
 
 impl<K, V> Operation<K, V> {
     #[inline]
     fn new(raw: RawOperation<K, V>) -> Self {
-        Self { raw, leaky: false }
+        Self {
+            raw,
+            leaky: false,
+            evicted: None,
+        }
     }
 
     #[inline]
     fn make_leaky(&mut self) {
         self.leaky = true;
     }
+
+    /// Returns whether the value evicted by this operation is still accessible through an
+    /// `Evicted`.
+    #[inline]
+    fn is_borrowed(&self) -> bool {
+        self.evicted
+            .as_ref()
+            .is_some_and(|evicted| Rc::strong_count(evicted) > 1)
+    }
 }
 
 enum RawOperation<K, V> {
     InsertUnique(Alias<K>, Alias<V>),
-    Replace(K, Alias<V>),
-    Remove(K),
+    // The key here is owned by the operation, and is dropped once the operation is replayed. It
+    // is aliased so that evicted values can hand out copies of it.
+    Replace(Alias<K>, Alias<V>),
+    // The key here is a copy of the key in the map
+    ReplaceAliased(Alias<K>, Alias<V>),
+    // The key here is owned by the operation, like for `Replace`
+    Remove(Alias<K>),
+    // The key here is a copy of the key in the other map, which is what actually gets dropped
+    RemoveAliased(Alias<K>),
     Drop(Alias<V>),
+    // Removes and drops every entry (or only removes them, if leaky), then resizes the map to the
+    // given capacity
+    Clear(usize),
+    // Removes and drops every entry, then inserts the given entries. These are the original copies
+    // of the entries of the other map, and are rehashed when replayed since the two maps may use
+    // different hashers.
+    Reset(Vec<(Alias<K>, Alias<V>)>),
 }
 
 /// A value which was evicted from a map.
@@ -445,8 +2110,9 @@ enum RawOperation<K, V> {
 /// is valid for reads for the duration of `'a`, which will never outlive the guard which is
 /// protecting the value. To use the evicted value after the associated guard is dropped, it must
 /// be [`leak`](crate::Evicted::leak)ed, at which point the programmer is responsible for dropping
-/// or claiming ownership of the value. If an evicted value is not leaked, then it will be dropped
-/// at some unspecified point after (or while) the guard is dropped when it is safe to do so.
+/// or claiming ownership of the value. If an evicted value is not leaked, including when it is
+/// simply ignored, then it will be dropped at some unspecified point after (or while) the guard is
+/// dropped when it is safe to do so.
 ///
 /// # Inspecting an evicted value
 ///
@@ -476,8 +2142,12 @@ enum RawOperation<K, V> {
 /// [`leak`](crate::Evicted::leak) and [`Leaked`](crate::Leaked) for more information.
 pub struct Evicted<'a, K, V> {
     leaked: Leaked<V>,
-    operations: &'a UnsafeCell<Vec<Operation<K, V>>>,
+    // A copy of the key stored in the operation which evicted the value
+    key: Alias<K>,
+    operations: &'a UnsafeCell<OperationLog<K, V>>,
     operation: usize,
+    // Keeps publishing from handing the value to readers to drop while it can still be read
+    _live: Option<Rc<()>>,
 }
 
 impl<'a, K, V> Evicted<'a, K, V> {
@@ -487,19 +2157,83 @@ impl<'a, K, V> Evicted<'a, K, V> {
         K: Eq + Hash,
         S: BuildHasher,
     {
-        let operations = &guard.handle.operations;
-        let operation = operations.with(|ops_ptr| unsafe { &*ops_ptr }.len() - 1);
+        let operation = guard
+            .handle
+            .operations
+            .with(|ops_ptr| unsafe { &*ops_ptr }.len() - 1);
+
+        unsafe {
+            Self::with_operation(&guard.handle.operations, guard.handle_uid, value, operation)
+        }
+    }
+
+    #[inline]
+    unsafe fn with_operation(
+        operations: &'a UnsafeCell<OperationLog<K, V>>,
+        handle_uid: WriterUid,
+        value: Alias<V>,
+        operation: usize,
+    ) -> Self {
+        // The operation may move when more operations are pushed, but the key it holds is only
+        // dropped once the operation is flushed, which happens after the guard is dropped
+        let (key, live) = operations.with_mut(|ops_ptr| {
+            let operations = unsafe { &mut *ops_ptr };
+            let live = operations.tracks_evicted.then(|| Rc::new(()));
+            let operation = unsafe { operations.get_unchecked_mut(operation) };
+            operation.evicted = live.clone();
+            let key = match &operation.raw {
+                RawOperation::Replace(key, _)
+                | RawOperation::Remove(key)
+                | RawOperation::ReplaceAliased(key, _)
+                | RawOperation::RemoveAliased(key) => unsafe { Alias::copy(key) },
+                RawOperation::InsertUnique(..)
+                | RawOperation::Drop(_)
+                | RawOperation::Clear(_)
+                | RawOperation::Reset(_) => {
+                    unreachable!("Operation did not evict a value")
+                }
+            };
+            (key, live)
+        });
 
         Self {
-            leaked: Leaked {
-                value,
-                handle_uid: guard.handle_uid,
-            },
+            leaked: Leaked { value, handle_uid },
+            key,
             operations,
             operation,
+            _live: live,
         }
     }
 
+    /// Returns a reference to the key of the entry the value was evicted from.
+    ///
+    /// This is an associated function rather than a method so that it does not shadow methods on
+    /// the evicted value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::{self, Evicted};
+    /// use std::collections::HashMap;
+    ///
+    /// let (mut write, read) = flashmap::new::<String, u32>();
+    /// write.guard().insert("a".to_owned(), 1);
+    ///
+    /// // Keep track of which key each leaked value came from
+    /// let mut leaked = HashMap::new();
+    /// let mut guard = write.guard();
+    /// if let Some(evicted) = guard.replace("a".to_owned(), |&value| value + 1) {
+    ///     leaked.insert(Evicted::key(&evicted).clone(), Evicted::leak(evicted));
+    /// }
+    /// guard.publish();
+    ///
+    /// assert_eq!(write.reclaim_one(leaked.remove("a").unwrap()), 1);
+    /// ```
+    #[inline]
+    pub fn key(evicted: &Self) -> &K {
+        &evicted.key
+    }
+
     /// Leaks the contained value, extending its lifetime until it is manually converted into an
     /// owned value or dropped.
     ///
@@ -536,12 +2270,37 @@ impl<'a, K, V> Evicted<'a, K, V> {
     /// write.guard().drop_lazily(b);
     /// ```
     pub fn leak(evicted: Self) -> Leaked<V> {
-        evicted
-            .operations
-            .with_mut(|ptr| unsafe { (*ptr).get_unchecked_mut(evicted.operation) }.make_leaky());
+        evicted.operations.with_mut(|ptr| {
+            unsafe { (&mut *ptr).get_unchecked_mut(evicted.operation) }.make_leaky()
+        });
 
         evicted.leaked
     }
+
+    /// Computes a value from the evicted value, consuming the `Evicted` without leaking it.
+    ///
+    /// The evicted value is only borrowed by `op`, and it is still dropped once no readers can
+    /// observe it, exactly as if the `Evicted` had been dropped. This is an associated function
+    /// rather than a method so that it does not shadow methods on the evicted value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::{self, Evicted};
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, String>();
+    /// write.guard().insert(1, "hello".to_owned());
+    ///
+    /// let len = write.guard().remove(1).map(|evicted| Evicted::map(evicted, String::len));
+    /// assert_eq!(len, Some(5));
+    /// ```
+    #[inline]
+    pub fn map<U, F>(evicted: Self, op: F) -> U
+    where
+        F: FnOnce(&V) -> U,
+    {
+        op(&evicted)
+    }
 }
 
 impl<K, V> Deref for Evicted<'_, K, V> {
@@ -607,6 +2366,42 @@ impl<V> Leaked<V> {
     pub fn into_inner(leaked: Self) -> Alias<V> {
         leaked.value
     }
+
+    /// Takes ownership of the leaked value and moves it into an [`Arc`](std::sync::Arc), waiting
+    /// until no readers can observe the value in the map.
+    ///
+    /// This is equivalent to calling [`WriteHandle::reclaim_one`](crate::WriteHandle::reclaim_one)
+    /// and wrapping the result, and is useful for sharing an evicted value with other parts of a
+    /// program.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the leaked value came from a different map than the one `handle` is associated
+    /// with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flashmap::{self, Evicted, Leaked};
+    /// use std::{sync::Arc, thread};
+    ///
+    /// let (mut write, read) = flashmap::new::<u32, Vec<u8>>();
+    /// write.guard().insert(1, vec![0; 1024]);
+    ///
+    /// let leaked = write.guard().remove(1).map(Evicted::leak).unwrap();
+    /// let shared: Arc<Vec<u8>> = Leaked::into_arc(leaked, &write);
+    ///
+    /// let shared2 = Arc::clone(&shared);
+    /// thread::spawn(move || assert_eq!(shared2.len(), 1024)).join().unwrap();
+    /// assert!(read.guard().is_empty());
+    /// ```
+    pub fn into_arc<K, S>(leaked: Self, handle: &WriteHandle<K, V, S>) -> std::sync::Arc<V>
+    where
+        K: Hash + Eq,
+        S: BuildHasher,
+    {
+        std::sync::Arc::new(handle.reclaim_one(leaked))
+    }
 }
 
 impl<V> Deref for Leaked<V> {
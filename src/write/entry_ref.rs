@@ -0,0 +1,122 @@
+use std::{
+    borrow::Borrow,
+    hash::{BuildHasher, Hash},
+    mem,
+};
+
+use hashbrown::hash_map;
+
+use super::{Operation, OperationLog, RawOccupiedEntryMut, RawOperation};
+use crate::{
+    loom::cell::UnsafeCell,
+    util::{Alias, BorrowHelper},
+    Map,
+};
+
+/// A view into a single entry in the map, which was looked up by a borrowed form of its key.
+///
+/// This type is the `flashmap` analog of `hashbrown`'s `EntryRef`, and is created via
+/// [`View::entry_ref`](crate::View::entry_ref). An owned key is only created if a value is
+/// inserted into a vacant entry.
+pub enum EntryRef<'a, 'q, K, Q: ?Sized, V, S> {
+    /// An occupied entry.
+    Occupied(RawOccupiedEntryMut<'a, K, V, S>),
+    /// A vacant entry.
+    Vacant(VacantEntryRef<'a, 'q, K, Q, V, S>),
+}
+
+impl<'a, 'q, K, Q, V, S> EntryRef<'a, 'q, K, Q, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: ToOwned<Owned = K> + ?Sized,
+    S: BuildHasher,
+{
+    /// Ensures a value is in the entry by inserting the default if it is vacant, and returns a
+    /// reference to the value in the entry.
+    #[inline]
+    pub fn or_insert(self, default: V) -> &'a V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Ensures a value is in the entry by inserting the result of the default function if it is
+    /// vacant, and returns a reference to the value in the entry.
+    #[inline]
+    pub fn or_insert_with<F>(self, default: F) -> &'a V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Self::Occupied(entry) => entry.into_key_value().1,
+            Self::Vacant(entry) => entry.insert(default()),
+        }
+    }
+}
+
+/// A view into a vacant entry in the map. It is part of the [`EntryRef`](crate::EntryRef) enum.
+pub struct VacantEntryRef<'a, 'q, K, Q: ?Sized, V, S> {
+    map: &'a mut Map<K, V, S>,
+    operations: &'a UnsafeCell<OperationLog<K, V>>,
+    key: &'q Q,
+}
+
+impl<'a, 'q, K, Q, V, S> VacantEntryRef<'a, 'q, K, Q, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: ToOwned<Owned = K> + ?Sized,
+    S: BuildHasher,
+{
+    #[inline]
+    pub(super) fn new(
+        map: &'a mut Map<K, V, S>,
+        operations: &'a UnsafeCell<OperationLog<K, V>>,
+        key: &'q Q,
+    ) -> Self {
+        Self {
+            map,
+            operations,
+            key,
+        }
+    }
+
+    /// Gets a reference to the borrowed key which was used to look up this entry.
+    #[inline]
+    pub fn key(&self) -> &'q Q {
+        self.key
+    }
+
+    /// Converts the borrowed key into an owned key, and inserts it into the map along with the
+    /// given value, returning a reference to the value.
+    #[inline]
+    pub fn insert(self, value: V) -> &'a V {
+        let key = self.key.to_owned();
+        let value = Alias::new(value);
+
+        // We look the key up again rather than inserting into the vacant slot we found earlier,
+        // since nothing guarantees that the owned key hashes and compares the same way as the
+        // borrowed key. Inserting a duplicate key would cause the two maps to diverge.
+        let (operation, inserted) = match self
+            .map
+            .raw_entry_mut()
+            .from_key(BorrowHelper::new_ref(&key))
+        {
+            hash_map::RawEntryMut::Vacant(entry) => {
+                let key = Alias::new(key);
+                let (_, inserted) =
+                    entry.insert(unsafe { Alias::copy(&key) }, unsafe { Alias::copy(&value) });
+                (RawOperation::InsertUnique(key, value), inserted)
+            }
+            hash_map::RawEntryMut::Occupied(entry) => {
+                let slot = entry.into_mut();
+                // The old value is dropped when the operation is applied to the other map
+                let _ = mem::replace(slot, unsafe { Alias::copy(&value) });
+                (RawOperation::Replace(Alias::new(key), value), slot)
+            }
+        };
+
+        self.operations.with_mut(|ops_ptr| {
+            unsafe { &mut *ops_ptr }.push(Operation::new(operation));
+        });
+
+        inserted
+    }
+}
@@ -0,0 +1,38 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    process::abort,
+};
+
+use super::{MapOp, Operation, RawOperation};
+
+pub(crate) type Observer<K, V> = Box<dyn Fn(&MapOp<&K, &V>) + Send + Sync>;
+
+/// Reports the given operations to the observer in the order they were made. The operations have
+/// already been applied to the writer's map, and cannot be undone, so the process is aborted if
+/// the observer panics.
+pub(super) fn notify<K, V>(observer: &Observer<K, V>, operations: &[Operation<K, V>]) {
+    let notify_all = || {
+        for operation in operations {
+            match &operation.raw {
+                RawOperation::InsertUnique(key, value) => observer(&MapOp::Insert(key, value)),
+                RawOperation::Replace(key, value) => observer(&MapOp::Replace(key, value)),
+                RawOperation::ReplaceAliased(key, value) => observer(&MapOp::Replace(key, value)),
+                RawOperation::Remove(key) => observer(&MapOp::Remove(key)),
+                RawOperation::RemoveAliased(key) => observer(&MapOp::Remove(key)),
+                RawOperation::Clear(_) => observer(&MapOp::Clear),
+                RawOperation::Reset(entries) => {
+                    observer(&MapOp::Clear);
+                    for (key, value) in entries {
+                        observer(&MapOp::Insert(key, value));
+                    }
+                }
+                // Reclaimed values were already removed from the map
+                RawOperation::Drop(_) => {}
+            }
+        }
+    };
+
+    if panic::catch_unwind(AssertUnwindSafe(notify_all)).is_err() {
+        abort();
+    }
+}
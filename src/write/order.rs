@@ -0,0 +1,159 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+};
+
+use super::{Operation, RawOperation};
+use crate::{
+    util::{Alias, BorrowHelper},
+    Map,
+};
+
+/// The insertion order of the keys in one copy of the map.
+///
+/// Removed keys leave behind a tombstone so that the positions of the other keys do not change.
+/// Every key here is an alias of a key in the corresponding copy of the map, so this must be kept
+/// in sync with that copy: a key must be tombstoned no later than when it is dropped.
+pub(crate) type InsertionOrder<K> = Vec<Option<Alias<K>>>;
+
+/// Iterates over the entries of the given map in the given insertion order.
+#[inline]
+pub(crate) fn iter_ordered<'a, K, V, S>(
+    map: &'a Map<K, V, S>,
+    order: &'a InsertionOrder<K>,
+) -> impl Iterator<Item = (&'a K, &'a V)> + 'a
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    order.iter().flatten().map(move |key| {
+        let (key, value) = map
+            .get_key_value(BorrowHelper::new_ref(&**key))
+            .expect("Insertion order is out of sync with the map");
+        (&**key, &**value)
+    })
+}
+
+// Don't bother compacting tiny orders
+const MIN_COMPACTION_LEN: usize = 32;
+
+enum OrderOperation<K> {
+    Push(Alias<K>),
+    Tombstone(usize),
+    Clear,
+    Compact,
+}
+
+/// Maintains the insertion order for the writer.
+///
+/// When changes are published, the writer derives the changes to the insertion order from the
+/// operation log and applies them to the writer's copy of the order. Those changes are recorded
+/// here, and replayed on the other copy once it becomes available, just like the operation log.
+pub(super) struct OrderTracker<K> {
+    // Maps every key to its position in the order. Since both copies of the order undergo the
+    // same changes, positions are the same in both copies once they are synchronized.
+    positions: hashbrown::HashMap<Alias<K>, usize, RandomState>,
+    tombstones: usize,
+    pending: Vec<OrderOperation<K>>,
+}
+
+impl<K> OrderTracker<K>
+where
+    K: Hash + Eq,
+{
+    pub(super) fn new() -> Self {
+        Self {
+            positions: hashbrown::HashMap::with_hasher(RandomState::new()),
+            tombstones: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Applies the changes made by the given operations to the writer's copy of the order.
+    ///
+    /// # Safety
+    ///
+    /// No readers may be viewing the given order, and every change made to it since the last call
+    /// to this function must have been recorded in `operations`.
+    pub(super) unsafe fn record<V>(
+        &mut self,
+        operations: &[Operation<K, V>],
+        order: &mut InsertionOrder<K>,
+    ) {
+        for operation in operations {
+            let position = match &operation.raw {
+                RawOperation::InsertUnique(key, _) => {
+                    self.positions
+                        .insert(unsafe { Alias::copy(key) }, order.len());
+                    order.push(Some(unsafe { Alias::copy(key) }));
+                    self.pending
+                        .push(OrderOperation::Push(unsafe { Alias::copy(key) }));
+                    continue;
+                }
+                RawOperation::Remove(key) | RawOperation::RemoveAliased(key) => {
+                    self.positions.remove(key)
+                }
+                RawOperation::Clear(_) => {
+                    self.positions.clear();
+                    self.tombstones = 0;
+                    order.clear();
+                    self.pending.push(OrderOperation::Clear);
+                    continue;
+                }
+                RawOperation::Reset(entries) => {
+                    self.positions.clear();
+                    self.tombstones = 0;
+                    order.clear();
+                    self.pending.push(OrderOperation::Clear);
+
+                    for (key, _) in entries {
+                        self.positions
+                            .insert(unsafe { Alias::copy(key) }, order.len());
+                        order.push(Some(unsafe { Alias::copy(key) }));
+                        self.pending
+                            .push(OrderOperation::Push(unsafe { Alias::copy(key) }));
+                    }
+                    continue;
+                }
+                RawOperation::Replace(..)
+                | RawOperation::ReplaceAliased(..)
+                | RawOperation::Drop(_) => continue,
+            };
+
+            let position = position.expect("Removed key is missing from the insertion order");
+            order[position] = None;
+            self.tombstones += 1;
+            self.pending.push(OrderOperation::Tombstone(position));
+        }
+
+        if order.len() >= MIN_COMPACTION_LEN && self.tombstones > order.len() / 2 {
+            order.retain(Option::is_some);
+            self.tombstones = 0;
+
+            self.positions.clear();
+            for (position, key) in order.iter().flatten().enumerate() {
+                self.positions.insert(unsafe { Alias::copy(key) }, position);
+            }
+
+            self.pending.push(OrderOperation::Compact);
+        }
+    }
+
+    /// Replays the changes recorded since the last call to this function on the other copy of the
+    /// order.
+    ///
+    /// # Safety
+    ///
+    /// No readers may be viewing the given order, and it must be the copy which was not passed to
+    /// the last call to [`record`](OrderTracker::record).
+    pub(super) unsafe fn replay(&mut self, order: &mut InsertionOrder<K>) {
+        for operation in self.pending.drain(..) {
+            match operation {
+                OrderOperation::Push(key) => order.push(Some(key)),
+                OrderOperation::Tombstone(position) => order[position] = None,
+                OrderOperation::Clear => order.clear(),
+                OrderOperation::Compact => order.retain(Option::is_some),
+            }
+        }
+    }
+}
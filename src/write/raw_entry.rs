@@ -0,0 +1,244 @@
+use std::{
+    borrow::Borrow,
+    hash::{BuildHasher, Hash},
+    mem,
+};
+
+use hashbrown::hash_map;
+
+use super::{Evicted, Operation, OperationLog, RawOperation, WriterUid};
+use crate::{
+    loom::cell::UnsafeCell,
+    util::{Alias, BorrowHelper},
+};
+
+type Operations<K, V> = UnsafeCell<OperationLog<K, V>>;
+
+/// A builder for computing where in the map a key-value pair would be stored.
+///
+/// This type is the `flashmap` analog of `hashbrown`'s `RawEntryBuilderMut`, and is created via
+/// [`View::raw_entry_mut`](crate::View::raw_entry_mut). Every modification made through the
+/// resulting entries is recorded, so it is eventually applied to both copies of the map.
+pub struct RawEntryBuilderMut<'a, K, V, S> {
+    builder: hash_map::RawEntryBuilderMut<'a, Alias<K>, Alias<V>, S>,
+    operations: &'a Operations<K, V>,
+    handle_uid: WriterUid,
+}
+
+impl<'a, K, V, S> RawEntryBuilderMut<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    #[inline]
+    pub(super) fn new(
+        builder: hash_map::RawEntryBuilderMut<'a, Alias<K>, Alias<V>, S>,
+        operations: &'a Operations<K, V>,
+        handle_uid: WriterUid,
+    ) -> Self {
+        Self {
+            builder,
+            operations,
+            handle_uid,
+        }
+    }
+
+    /// Creates a [`RawEntryMut`](crate::RawEntryMut) from the given key.
+    #[inline]
+    pub fn from_key<Q>(self, key: &Q) -> RawEntryMut<'a, K, V, S>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.builder.from_key(BorrowHelper::new_ref(key));
+        RawEntryMut::new(entry, self.operations, self.handle_uid)
+    }
+
+    /// Creates a [`RawEntryMut`](crate::RawEntryMut) from the given key and its hash.
+    ///
+    /// # Safety
+    ///
+    /// `hash` must be the hash of `key` as computed by the map's hasher. Searching with the wrong
+    /// hash may report an entry as vacant when it is not, and inserting into that entry would
+    /// leave the two copies of the map in inconsistent states.
+    #[inline]
+    pub unsafe fn from_key_hashed_nocheck<Q>(self, hash: u64, key: &Q) -> RawEntryMut<'a, K, V, S>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self
+            .builder
+            .from_key_hashed_nocheck(hash, BorrowHelper::new_ref(key));
+        RawEntryMut::new(entry, self.operations, self.handle_uid)
+    }
+
+    /// Creates a [`RawEntryMut`](crate::RawEntryMut) from the given hash and matching function.
+    ///
+    /// # Safety
+    ///
+    /// `hash` must be the hash of the key being searched for as computed by the map's hasher, and
+    /// `is_match` must return `true` if and only if it is given a key equal to that key. If the
+    /// returned entry is vacant, then only a key equal to that key may be inserted into it.
+    #[inline]
+    pub unsafe fn from_hash<F>(self, hash: u64, mut is_match: F) -> RawEntryMut<'a, K, V, S>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        let entry = self.builder.from_hash(hash, |key| is_match(key));
+        RawEntryMut::new(entry, self.operations, self.handle_uid)
+    }
+}
+
+/// A view into a single entry in the map, which may either be vacant or occupied.
+///
+/// See [`View::raw_entry_mut`](crate::View::raw_entry_mut) for examples.
+pub enum RawEntryMut<'a, K, V, S> {
+    /// An occupied entry.
+    Occupied(RawOccupiedEntryMut<'a, K, V, S>),
+    /// A vacant entry.
+    Vacant(RawVacantEntryMut<'a, K, V, S>),
+}
+
+impl<'a, K, V, S> RawEntryMut<'a, K, V, S> {
+    #[inline]
+    fn new(
+        entry: hash_map::RawEntryMut<'a, Alias<K>, Alias<V>, S>,
+        operations: &'a Operations<K, V>,
+        handle_uid: WriterUid,
+    ) -> Self {
+        match entry {
+            hash_map::RawEntryMut::Occupied(entry) => Self::Occupied(RawOccupiedEntryMut {
+                entry,
+                operations,
+                handle_uid,
+            }),
+            hash_map::RawEntryMut::Vacant(entry) => {
+                Self::Vacant(RawVacantEntryMut { entry, operations })
+            }
+        }
+    }
+}
+
+/// A view into an occupied entry in the map. It is part of the [`RawEntryMut`](crate::RawEntryMut)
+/// enum.
+pub struct RawOccupiedEntryMut<'a, K, V, S> {
+    entry: hash_map::RawOccupiedEntryMut<'a, Alias<K>, Alias<V>, S>,
+    operations: &'a Operations<K, V>,
+    handle_uid: WriterUid,
+}
+
+impl<'a, K, V, S> RawOccupiedEntryMut<'a, K, V, S> {
+    /// Gets a reference to the key in the entry.
+    #[inline]
+    pub fn key(&self) -> &K {
+        self.entry.key()
+    }
+
+    /// Gets a reference to the value in the entry.
+    #[inline]
+    pub fn get(&self) -> &V {
+        self.entry.get()
+    }
+
+    /// Converts the entry into references to its key and value with the lifetime of the map
+    /// borrow.
+    #[inline]
+    pub fn into_key_value(self) -> (&'a K, &'a V) {
+        let (key, value) = self.entry.into_key_value();
+        (key, value)
+    }
+
+    #[inline]
+    fn push(&self, operation: RawOperation<K, V>) -> usize {
+        self.operations.with_mut(|ops_ptr| {
+            let operations = unsafe { &mut *ops_ptr };
+            operations.push(Operation::new(operation));
+            operations.len() - 1
+        })
+    }
+
+    /// Sets the value of the entry, and returns the evicted value. See
+    /// [`Evicted`](crate::Evicted) for details.
+    #[inline]
+    pub fn insert(&mut self, value: V) -> Evicted<'a, K, V> {
+        let value = Alias::new(value);
+        let operation = self.push(RawOperation::ReplaceAliased(
+            unsafe { Alias::copy(self.entry.key()) },
+            unsafe { Alias::copy(&value) },
+        ));
+        let old = mem::replace(self.entry.get_mut(), value);
+
+        unsafe { Evicted::with_operation(self.operations, self.handle_uid, old, operation) }
+    }
+
+    /// Removes the entry from the map, and returns the evicted value. See
+    /// [`Evicted`](crate::Evicted) for details.
+    #[inline]
+    pub fn remove(self) -> Evicted<'a, K, V> {
+        let operations = self.operations;
+        let handle_uid = self.handle_uid;
+        let (key, value) = self.entry.remove_entry();
+
+        let operation = operations.with_mut(|ops_ptr| {
+            let operations = unsafe { &mut *ops_ptr };
+            operations.push(Operation::new(RawOperation::RemoveAliased(key)));
+            operations.len() - 1
+        });
+
+        unsafe { Evicted::with_operation(operations, handle_uid, value, operation) }
+    }
+}
+
+/// A view into a vacant entry in the map. It is part of the [`RawEntryMut`](crate::RawEntryMut)
+/// enum.
+pub struct RawVacantEntryMut<'a, K, V, S> {
+    entry: hash_map::RawVacantEntryMut<'a, Alias<K>, Alias<V>, S>,
+    operations: &'a Operations<K, V>,
+}
+
+impl<'a, K, V, S> RawVacantEntryMut<'a, K, V, S>
+where
+    K: Hash,
+    S: BuildHasher,
+{
+    /// Inserts the given key-value pair into the map, returning references to them.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be equal to the key used to look up this entry. Inserting a key which is
+    /// already present in the map would cause the two copies of the map to diverge when the
+    /// operation is replayed.
+    #[inline]
+    pub unsafe fn insert(self, key: K, value: V) -> (&'a K, &'a V) {
+        let (key, value) = self.entry.insert(Alias::new(key), Alias::new(value));
+        self.operations.with_mut(|ops_ptr| {
+            unsafe { &mut *ops_ptr }.push(Operation::new(RawOperation::InsertUnique(
+                unsafe { Alias::copy(key) },
+                unsafe { Alias::copy(value) },
+            )))
+        });
+        (key, value)
+    }
+
+    /// Inserts the given key-value pair into the map with the given hash, returning references
+    /// to them.
+    ///
+    /// # Safety
+    ///
+    /// `hash` must be the hash of `key` as computed by the map's hasher, and the safety
+    /// requirements of [`insert`](crate::RawVacantEntryMut::insert) must be upheld.
+    #[inline]
+    pub unsafe fn insert_hashed_nocheck(self, hash: u64, key: K, value: V) -> (&'a K, &'a V) {
+        let (key, value) =
+            self.entry
+                .insert_hashed_nocheck(hash, Alias::new(key), Alias::new(value));
+        self.operations.with_mut(|ops_ptr| {
+            unsafe { &mut *ops_ptr }.push(Operation::new(RawOperation::InsertUnique(
+                unsafe { Alias::copy(key) },
+                unsafe { Alias::copy(value) },
+            )))
+        });
+        (key, value)
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    future::Future,
+    hash::{BuildHasher, Hash},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use super::WriteHandle;
+
+/// The future returned by
+/// [`WriteHandle::synchronize_timeout_async`](crate::WriteHandle::synchronize_timeout_async).
+///
+/// Resolves to `true` once all readers see the same version of the map, or to `false` if the
+/// timer finishes first. Dropping this future before it resolves abandons the wait, leaving the
+/// write handle usable as normal.
+#[must_use = "futures do nothing unless polled"]
+pub struct SynchronizeTimeout<'a, K, V, S, T>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    handle: &'a mut WriteHandle<K, V, S>,
+    timer: T,
+    waiting: bool,
+    done: bool,
+}
+
+impl<'a, K, V, S, T> SynchronizeTimeout<'a, K, V, S, T>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub(super) fn new(handle: &'a mut WriteHandle<K, V, S>, timer: T) -> Self {
+        Self {
+            handle,
+            timer,
+            waiting: false,
+            done: false,
+        }
+    }
+}
+
+impl<K, V, S, T> Future for SynchronizeTimeout<'_, K, V, S, T>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+    T: Future,
+{
+    type Output = bool;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<bool> {
+        // SAFETY: the timer is never moved out of `self`, and no other field is pinned
+        let this = unsafe { self.get_unchecked_mut() };
+        assert!(!this.done, "SynchronizeTimeout polled after completion");
+
+        let core = &this.handle.core;
+        if core.poll_synchronize(&mut this.waiting, cx).is_ready() {
+            this.done = true;
+            return Poll::Ready(true);
+        }
+
+        let timer = unsafe { Pin::new_unchecked(&mut this.timer) };
+        if timer.poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        this.waiting = false;
+        this.done = true;
+        Poll::Ready(core.stop_waiting())
+    }
+}
+
+impl<K, V, S, T> Drop for SynchronizeTimeout<'_, K, V, S, T>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn drop(&mut self) {
+        if self.waiting {
+            self.handle.core.stop_waiting();
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    borrow::Borrow,
+    collections::hash_map::RandomState,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    hash::{BuildHasher, Hash},
+};
+
+use super::{Operation, RawOperation};
+use crate::{
+    util::{Alias, BorrowHelper},
+    Map,
+};
+
+/// The version of every key in one copy of the map.
+///
+/// Every key here is an alias of a key in the corresponding copy of the map, so this must be kept
+/// in sync with that copy: a key must be removed no later than when it is dropped.
+pub(crate) type EntryVersions<K> = hashbrown::HashMap<Alias<K>, u64, RandomState>;
+
+/// Looks up the value and version of the given key in the given map.
+#[inline]
+pub(crate) fn get_versioned<'a, K, Q, V, S>(
+    map: &'a Map<K, V, S>,
+    versions: &'a EntryVersions<K>,
+    key: &Q,
+) -> Option<(&'a V, u64)>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    let value = map.get(BorrowHelper::new_ref(key))?;
+    let version = versions
+        .get(BorrowHelper::new_ref(key))
+        .expect("Entry versions are out of sync with the map");
+    Some((&**value, *version))
+}
+
+/// The error returned by [`View::replace_if_version`](crate::View::replace_if_version) when the
+/// entry was changed since the expected version was observed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VersionMismatch {
+    current: Option<u64>,
+}
+
+impl VersionMismatch {
+    #[inline]
+    pub(super) fn new(current: Option<u64>) -> Self {
+        Self { current }
+    }
+
+    /// Returns the current version of the entry, or `None` if the key is not in the map.
+    #[inline]
+    pub fn current(&self) -> Option<u64> {
+        self.current
+    }
+}
+
+impl Display for VersionMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.current {
+            Some(version) => write!(f, "entry was changed (current version is {version})"),
+            None => f.write_str("entry was removed"),
+        }
+    }
+}
+
+impl Error for VersionMismatch {}
+
+enum VersionOperation<K> {
+    Set(Alias<K>, u64),
+    Remove(Alias<K>),
+    Clear,
+}
+
+/// Maintains the entry versions for the writer.
+///
+/// Changes to the versions are derived from the operation log and applied to the writer's copy of
+/// the versions. Those changes are recorded here, and replayed on the other copy once it becomes
+/// available, just like the operation log.
+pub(super) struct VersionTracker<K> {
+    next_version: u64,
+    // The number of operations in the log which were already applied to the writer's copy
+    recorded: usize,
+    pending: Vec<VersionOperation<K>>,
+}
+
+impl<K> VersionTracker<K>
+where
+    K: Hash + Eq,
+{
+    pub(super) fn new() -> Self {
+        Self {
+            next_version: 0,
+            recorded: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Applies the changes made by the operations which were not yet recorded to the writer's copy
+    /// of the versions. Every change is assigned a new version.
+    ///
+    /// # Safety
+    ///
+    /// No readers may be viewing the given versions, and `operations` must be the writer's
+    /// operation log, which was not flushed since the last call to
+    /// [`replay`](VersionTracker::replay).
+    pub(super) unsafe fn record<V>(
+        &mut self,
+        operations: &[Operation<K, V>],
+        versions: &mut EntryVersions<K>,
+    ) {
+        for operation in &operations[self.recorded..] {
+            let version = self.next_version;
+
+            let change = match &operation.raw {
+                RawOperation::InsertUnique(key, _) => {
+                    versions.insert(unsafe { Alias::copy(key) }, version);
+                    VersionOperation::Set(unsafe { Alias::copy(key) }, version)
+                }
+                RawOperation::Replace(key, _) | RawOperation::ReplaceAliased(key, _) => {
+                    Self::update(versions, key, version)
+                }
+                RawOperation::Remove(key) => {
+                    let (key, _) = versions
+                        .remove_entry(key)
+                        .expect("Removed key is missing from the entry versions");
+                    VersionOperation::Remove(key)
+                }
+                RawOperation::RemoveAliased(key) => {
+                    versions.remove(key);
+                    VersionOperation::Remove(unsafe { Alias::copy(key) })
+                }
+                RawOperation::Clear(_) => {
+                    versions.clear();
+                    VersionOperation::Clear
+                }
+                RawOperation::Reset(entries) => {
+                    versions.clear();
+                    self.pending.push(VersionOperation::Clear);
+
+                    for (key, _) in entries {
+                        versions.insert(unsafe { Alias::copy(key) }, self.next_version);
+                        self.pending.push(VersionOperation::Set(
+                            unsafe { Alias::copy(key) },
+                            self.next_version,
+                        ));
+                        self.next_version += 1;
+                    }
+                    continue;
+                }
+                RawOperation::Drop(_) => continue,
+            };
+
+            self.pending.push(change);
+            self.next_version += 1;
+        }
+
+        self.recorded = operations.len();
+    }
+
+    fn update<Q>(versions: &mut EntryVersions<K>, key: &Q, version: u64) -> VersionOperation<K>
+    where
+        Alias<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (key, slot) = versions
+            .get_key_value_mut(key)
+            .expect("Replaced key is missing from the entry versions");
+        *slot = version;
+        VersionOperation::Set(unsafe { Alias::copy(key) }, version)
+    }
+
+    /// Replays the changes recorded since the last call to this function on the other copy of the
+    /// versions. This must be called before the operation log is flushed, since the recorded
+    /// changes refer to keys which may be dropped by the flush.
+    ///
+    /// # Safety
+    ///
+    /// No readers may be viewing the given versions, and it must be the copy which was not passed
+    /// to the last call to [`record`](VersionTracker::record).
+    pub(super) unsafe fn replay(&mut self, versions: &mut EntryVersions<K>) {
+        for change in self.pending.drain(..) {
+            match change {
+                VersionOperation::Set(key, version) => {
+                    versions.insert(key, version);
+                }
+                VersionOperation::Remove(key) => {
+                    versions.remove(&key);
+                }
+                VersionOperation::Clear => versions.clear(),
+            }
+        }
+
+        self.recorded = 0;
+    }
+}
@@ -6,7 +6,13 @@
 
 mod util;
 
-use std::ops::Deref;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    ops::Deref,
+    sync::Arc,
+    thread,
+};
 
 #[test]
 pub fn insert() {
@@ -286,3 +292,2187 @@ fn invalid_lazy_drop() {
     w2.guard().drop_lazily(leaked);
     drop(guard);
 }
+
+#[test]
+fn handle_contains_key() {
+    let (mut write, read) = flashmap::new::<Box<i32>, Box<i32>>();
+    assert!(!write.contains_key(&1));
+
+    let mut guard = write.guard();
+    guard.insert(Box::new(1), Box::new(2));
+    guard.insert(Box::new(2), Box::new(4));
+    drop(guard);
+    assert!(write.contains_key(&1));
+    assert!(write.contains_key(&2));
+
+    let read_guard = read.guard();
+    write.guard().remove(Box::new(1));
+    assert!(!write.contains_key(&1));
+    assert!(write.contains_key(&2));
+    assert!(read_guard.contains_key(&1));
+}
+
+#[test]
+fn extract_if() {
+    let (mut write, read) = flashmap::new::<Box<i32>, Box<i32>>();
+
+    let mut guard = write.guard();
+    for i in 0..16 {
+        guard.insert(Box::new(i), Box::new(i * 2));
+    }
+    drop(guard);
+
+    let mut guard = write.guard();
+    let extracted = guard.extract_if(|k, _| **k % 4 == 0);
+    assert_eq!(extracted.len(), 4);
+    for (k, v) in &extracted {
+        assert_eq!(***v, **k * 2);
+    }
+    let leaked = extracted
+        .into_iter()
+        .find(|(k, _)| **k == 8)
+        .map(|(_, v)| flashmap::Evicted::leak(v))
+        .unwrap();
+    drop(guard);
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 12);
+    assert!(!guard.contains_key(&0));
+    assert!(!guard.contains_key(&8));
+    assert_eq!(**guard.get(&9).unwrap(), 18);
+    drop(guard);
+
+    assert_eq!(*write.reclaim_one(leaked), 16);
+    assert_eq!(write.guard().len(), 12);
+}
+
+#[test]
+fn peek() {
+    let (mut write, read) = flashmap::new::<Box<i32>, Box<i32>>();
+    assert!(write.peek(|view| view.is_empty()));
+
+    write.guard().insert(Box::new(1), Box::new(2));
+    assert_eq!(write.peek(|view| view.get(&1).map(|v| **v)), Some(2));
+
+    let read_guard = read.guard();
+    write.guard().replace(Box::new(1), |_| Box::new(3));
+    assert_eq!(write.peek(|view| view.get(&1).map(|v| **v)), Some(3));
+    assert_eq!(**read_guard.get(&1).unwrap(), 2);
+}
+
+#[test]
+fn raw_entry_mut() {
+    use flashmap::RawEntryMut;
+
+    let (mut write, read) = flashmap::new::<Box<i32>, Box<i32>>();
+
+    let mut guard = write.guard();
+    for i in 0..4 {
+        match guard.raw_entry_mut().from_key(&i) {
+            RawEntryMut::Occupied(_) => panic!("key {i} should be vacant"),
+            RawEntryMut::Vacant(entry) => unsafe {
+                entry.insert(Box::new(i), Box::new(i * 2));
+            },
+        }
+    }
+    drop(guard);
+
+    let mut guard = write.guard();
+    match guard.raw_entry_mut().from_key(&1) {
+        RawEntryMut::Occupied(mut entry) => assert_eq!(**entry.insert(Box::new(10)), 2),
+        RawEntryMut::Vacant(_) => panic!("key 1 should be occupied"),
+    }
+    match guard.raw_entry_mut().from_key(&2) {
+        RawEntryMut::Occupied(entry) => assert_eq!(**entry.remove(), 4),
+        RawEntryMut::Vacant(_) => panic!("key 2 should be occupied"),
+    }
+    drop(guard);
+
+    // Make sure the operations were replayed on both maps
+    for _ in 0..2 {
+        let guard = read.guard();
+        assert_eq!(guard.len(), 3);
+        assert_eq!(**guard.get(&1).unwrap(), 10);
+        assert!(guard.get(&2).is_none());
+        assert_eq!(**guard.get(&3).unwrap(), 6);
+        drop(guard);
+        drop(write.guard());
+    }
+}
+
+#[test]
+fn eq_std_hash_map() {
+    use std::collections::HashMap;
+
+    let (mut write, read) = flashmap::new::<Box<i32>, Box<i32>>();
+    let mut oracle = HashMap::new();
+    assert!(read.guard() == oracle);
+
+    let mut guard = write.guard();
+    for i in 0..8 {
+        guard.insert(Box::new(i), Box::new(i * 2));
+        oracle.insert(Box::new(i), Box::new(i * 2));
+    }
+    guard.remove(Box::new(3));
+    oracle.remove(&3);
+    assert!(guard == oracle);
+    drop(guard);
+
+    assert!(read.guard() == oracle);
+    assert!(oracle == read.guard());
+
+    oracle.insert(Box::new(4), Box::new(0));
+    assert!(read.guard() != oracle);
+    oracle.remove(&4);
+    assert!(read.guard() != oracle);
+}
+
+#[test]
+fn get_many() {
+    let (mut write, read) = flashmap::new::<Box<i32>, Box<i32>>();
+
+    let mut guard = write.guard();
+    for i in 0..4 {
+        guard.insert(Box::new(i), Box::new(i * 2));
+    }
+    drop(guard);
+
+    let guard = read.guard();
+    let [a, b, c] = guard.get_many([&0, &3, &4]);
+    assert_eq!(a.map(Deref::deref), Some(&0));
+    assert_eq!(b.map(Deref::deref), Some(&6));
+    assert!(c.is_none());
+
+    let (a, b) = guard.get_pair(&1, &1);
+    assert_eq!(a.map(Deref::deref), Some(&2));
+    assert_eq!(a, b);
+}
+
+#[test]
+fn get_cloned() {
+    let (mut write, read) = flashmap::new::<u32, Arc<u32>>();
+    let value = Arc::new(1);
+    write.guard().insert(1, Arc::clone(&value));
+
+    let cloned = read.guard().get_cloned(&1).unwrap();
+    assert!(Arc::ptr_eq(&cloned, &value));
+    assert_eq!(read.guard().get_cloned(&2), None);
+    assert!(Arc::ptr_eq(&write.guard().get_cloned(&1).unwrap(), &value));
+
+    // The clone outlives the entry it was taken from
+    write.guard().remove(1);
+    write.flush();
+    write.guard();
+    assert_eq!(Arc::strong_count(&value), 2);
+    assert_eq!(*cloned, 1);
+}
+
+#[test]
+fn get_or_err() {
+    let (mut write, read) = flashmap::new::<String, u32>();
+    write.guard().insert("a".to_owned(), 1);
+
+    let mut calls = 0;
+    let guard = read.guard();
+    assert_eq!(
+        guard.get_or_err("a", || {
+            calls += 1;
+            "missing"
+        }),
+        Ok(&1)
+    );
+    assert_eq!(calls, 0);
+    assert_eq!(
+        guard.get_or_err("b", || {
+            calls += 1;
+            "missing"
+        }),
+        Err("missing")
+    );
+    assert_eq!(calls, 1);
+    drop(guard);
+
+    let mut guard = write.guard();
+    guard.insert("b".to_owned(), 2);
+    assert_eq!(guard.get_or_err("b", || ()), Ok(&2));
+}
+
+#[test]
+fn get_flatten() {
+    let (mut write, read) = flashmap::new::<u32, Option<Box<u32>>>();
+
+    let mut guard = write.guard();
+    guard.insert(1, Some(Box::new(1)));
+    guard.insert(2, None);
+    guard.publish();
+
+    let guard = read.guard();
+    assert_eq!(guard.get_flatten(&1).map(|value| **value), Some(1));
+    assert_eq!(guard.get_flatten(&2), None);
+    assert_eq!(guard.get_flatten(&3), None);
+    assert_eq!(guard.get(&2), Some(&None));
+    drop(guard);
+
+    let mut guard = write.guard();
+    guard.replace(1, |_| None);
+    guard.insert(2, Some(Box::new(2)));
+    assert_eq!(guard.get_flatten(&1), None);
+    guard.publish();
+
+    let guard = read.guard();
+    assert_eq!(guard.get_flatten(&1), None);
+    assert_eq!(guard.get_flatten(&2).map(|value| **value), Some(2));
+}
+
+#[test]
+fn map_values() {
+    let (mut write, read) = flashmap::new::<Box<i32>, Box<i32>>();
+
+    let mut guard = write.guard();
+    for i in 0..8 {
+        guard.insert(Box::new(i), Box::new(i));
+    }
+    drop(guard);
+
+    let old_guard = read.guard();
+    write.guard().map_values(|k, v| Box::new(**k + **v));
+    for (k, v) in old_guard.iter() {
+        assert_eq!(**k, **v);
+    }
+    drop(old_guard);
+
+    write.guard().map_values(|_, v| Box::new(**v * 10));
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 8);
+    for (k, v) in guard.iter() {
+        assert_eq!(**v, **k * 20);
+    }
+}
+
+#[test]
+fn batch() {
+    let (mut write, read) = flashmap::new::<Box<i32>, Box<i32>>();
+
+    let mut batch = write.batch();
+    batch.guard().insert(Box::new(1), Box::new(1));
+    let mut guard = batch.guard();
+    guard.insert(Box::new(2), Box::new(2));
+    assert_eq!(guard.len(), 2);
+    guard.publish();
+    assert!(read.guard().is_empty());
+
+    let mut guard = batch.guard();
+    let leaked = guard
+        .replace(Box::new(1), |_| Box::new(10))
+        .map(flashmap::Evicted::leak)
+        .unwrap();
+    guard.drop_lazily(leaked);
+    drop(guard);
+    drop(batch);
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 2);
+    assert_eq!(**guard.get(&1).unwrap(), 10);
+    assert_eq!(**guard.get(&2).unwrap(), 2);
+    drop(guard);
+
+    // The standby map must have the whole batch replayed on it as well
+    write.guard().remove(Box::new(2));
+    let guard = read.guard();
+    assert_eq!(guard.len(), 1);
+    assert_eq!(**guard.get(&1).unwrap(), 10);
+}
+
+#[test]
+#[should_panic]
+#[cfg(not(miri))] // This test leaks memory, but that's expected
+fn invalid_reclaim_all() {
+    let (mut w1, _r1) = flashmap::new::<Box<i32>, Box<i32>>();
+    let (mut w2, _r2) = flashmap::new::<Box<i32>, Box<i32>>();
+
+    let mut guard = w1.guard();
+    guard.insert(Box::new(1), Box::new(1));
+    let first = guard.remove(Box::new(1)).map(flashmap::Evicted::leak);
+    drop(guard);
+
+    let mut guard = w2.guard();
+    guard.insert(Box::new(1), Box::new(1));
+    let second = guard.remove(Box::new(1)).map(flashmap::Evicted::leak);
+    drop(guard);
+
+    w2.reclaim_all(vec![second.unwrap(), first.unwrap()]);
+}
+
+#[test]
+fn tracked_guard() {
+    let (mut write, read) = flashmap::new::<String, u32>();
+
+    let mut guard = write.guard();
+    guard.insert("a".to_owned(), 1);
+    guard.insert("b".to_owned(), 2);
+    guard.publish();
+
+    let tracked = read.tracked_guard();
+    assert_eq!(tracked.get("a"), Some(&1));
+    assert!(tracked.contains_key("b"));
+    assert_eq!(tracked.get("c"), None);
+    assert_eq!(tracked.get("a"), Some(&1));
+    assert_eq!(tracked.into_read_set(), ["a", "b", "c", "a"]);
+
+    // The guard is released by into_read_set, so the writer can proceed
+    write.guard().remove("a".to_owned());
+    write.guard().remove("b".to_owned());
+    assert!(read.tracked_guard().into_read_set().is_empty());
+    assert!(read.guard().is_empty());
+}
+
+#[test]
+fn publish_and_synchronize() {
+    let (mut write, read) = flashmap::new::<Box<u32>, Box<u32>>();
+
+    let mut guard = write.guard();
+    guard.insert(Box::new(1), Box::new(1));
+    guard.publish_and_synchronize();
+
+    let old_guard = read.guard();
+    let handle = thread::spawn(move || {
+        let mut guard = write.guard();
+        guard.insert(Box::new(2), Box::new(2));
+        guard.publish_and_synchronize();
+        write
+    });
+
+    assert!(old_guard.get(&2).is_none());
+    drop(old_guard);
+
+    let write = handle.join().unwrap();
+    assert_eq!(**read.guard().get(&2).unwrap(), 2);
+    assert!(write.contains_key(&2));
+}
+
+#[test]
+fn view_eq_and_hash() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let (mut w1, r1) = flashmap::new::<u32, u32>();
+    let (mut w2, r2) = flashmap::Builder::new().with_seed(7).build::<u32, u32>();
+
+    let mut g1 = w1.guard();
+    let mut g2 = w2.guard();
+    for i in 0..50 {
+        g1.insert(i, i * 2);
+        g2.insert(49 - i, (49 - i) * 2);
+    }
+    g1.publish();
+    g2.publish();
+
+    assert!(r1.guard() == r2.guard());
+    assert_eq!(hash_of(&r1.guard()), hash_of(&r2.guard()));
+    assert_eq!(r1.guard().content_hash(), r2.guard().content_hash());
+
+    w2.guard().replace(0, |_| 1);
+    assert!(r1.guard() != r2.guard());
+    assert_ne!(hash_of(&r1.guard()), hash_of(&r2.guard()));
+    assert_ne!(r1.guard().content_hash(), r2.guard().content_hash());
+}
+
+#[test]
+fn trait_object_values() {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    type Handler = Box<dyn Fn(u32) -> u32 + Send + Sync>;
+
+    // Values which are neither Send nor Sync work on a single thread
+    let (mut write, read) = flashmap::new::<u32, Box<dyn Fn() -> u32>>();
+    write.guard().insert(1, Box::new(|| 10));
+    assert_eq!(read.guard().get(&1).unwrap()(), 10);
+    let reclaimed = write
+        .guard()
+        .remove(1)
+        .map(flashmap::Evicted::leak)
+        .unwrap();
+    assert_eq!(write.reclaim_one(reclaimed)(), 10);
+
+    let (mut write, read) = flashmap::new::<&'static str, Handler>();
+    let drops = Arc::new(AtomicU32::new(0));
+
+    struct CountDrop(Arc<AtomicU32>);
+
+    impl Drop for CountDrop {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let counter = CountDrop(Arc::clone(&drops));
+    let mut guard = write.guard();
+    guard.insert("double", Box::new(|x| x * 2));
+    guard.insert("square", Box::new(|x| x * x));
+    guard.insert(
+        "counted",
+        Box::new(move |x| {
+            let _ = &counter;
+            x
+        }),
+    );
+    guard.publish();
+
+    let dispatch = read.guard();
+    assert_eq!(dispatch.get("double").unwrap()(4), 8);
+    assert_eq!(dispatch.get("square").unwrap()(4), 16);
+    drop(dispatch);
+
+    // Evict, leak, and reclaim
+    let mut guard = write.guard();
+    let leaked = guard
+        .replace("double", |_| Box::new(|x| x + x + x))
+        .map(flashmap::Evicted::leak)
+        .unwrap();
+    assert_eq!(guard.remove("square").unwrap()(3), 9);
+    guard.publish();
+
+    let old_double: Handler = write.reclaim_one(leaked);
+    assert_eq!(old_double(5), 10);
+    assert_eq!(read.guard().get("double").unwrap()(5), 15);
+    assert!(read.guard().get("square").is_none());
+
+    // Lazily drop a trait object
+    let mut guard = write.guard();
+    let leaked = guard
+        .remove("counted")
+        .map(flashmap::Evicted::leak)
+        .unwrap();
+    guard.drop_lazily(leaked);
+    guard.publish();
+    assert_eq!(drops.load(Ordering::Relaxed), 0);
+
+    write.guard();
+    assert_eq!(drops.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn write_guard_len_reflects_staged_changes() {
+    let (mut write, read) = flashmap::new::<u32, u32>();
+
+    let mut guard = write.guard();
+    assert!(guard.is_empty());
+    guard.insert(1, 1);
+    guard.insert(2, 2);
+    assert_eq!(guard.len(), 2);
+
+    // Overwriting an existing key does not change the length
+    guard.insert(1, 10);
+    assert_eq!(guard.len(), 2);
+
+    // Neither does replacing or removing an absent key
+    assert!(guard.replace(3, |x| x + 1).is_none());
+    assert!(guard.remove(3).is_none());
+    assert_eq!(guard.len(), 2);
+    assert!(!guard.contains_key(&3));
+
+    guard.remove(2);
+    assert_eq!(guard.len(), 1);
+    assert_eq!(guard.get(&1), Some(&10));
+
+    // Readers do not see unpublished changes
+    assert!(read.guard().is_empty());
+    guard.publish();
+
+    // The next guard starts from the published state, replayed onto the standby map
+    let mut guard = write.guard();
+    assert_eq!(guard.len(), 1);
+    guard.insert(4, 4);
+    guard.remove(1);
+    guard.remove(4);
+    assert!(guard.is_empty());
+    guard.publish();
+
+    let mut batch = write.batch();
+    batch.guard().insert(5, 5);
+    let mut guard = batch.guard();
+    assert_eq!(guard.len(), 1);
+    guard.insert(6, 6);
+    assert_eq!(guard.len(), 2);
+    drop(guard);
+    batch.publish();
+
+    assert_eq!(read.guard().len(), 2);
+    assert_eq!(write.guard().len(), 2);
+}
+
+#[test]
+fn clear_and_shrink() {
+    let (mut write, read) = flashmap::new::<Box<u32>, Box<u32>>();
+
+    let mut guard = write.guard();
+    for i in 0..100 {
+        guard.insert(Box::new(i), Box::new(i * 2));
+    }
+    guard.publish();
+
+    let old_guard = read.guard();
+
+    let mut guard = write.guard();
+    let evicted = guard.remove(Box::new(0)).map(flashmap::Evicted::leak);
+    guard.clear_and_shrink(4);
+    assert!(guard.is_empty());
+    guard.insert(Box::new(1000), Box::new(1));
+    assert_eq!(guard.len(), 1);
+    guard.publish();
+
+    // Readers which started before the clear still see every entry
+    assert_eq!(old_guard.len(), 100);
+    assert_eq!(**old_guard.get(&50).unwrap(), 100);
+    drop(old_guard);
+
+    assert_eq!(*write.reclaim_one(evicted.unwrap()), 0);
+
+    // The clear is replayed on the standby map before it is written to
+    let mut guard = write.guard();
+    assert_eq!(guard.len(), 1);
+    guard.insert(Box::new(1001), Box::new(2));
+    guard.publish();
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 2);
+    assert_eq!(**guard.get(&1000).unwrap(), 1);
+    assert_eq!(**guard.get(&1001).unwrap(), 2);
+}
+
+#[test]
+fn synchronize_timeout() {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let (mut write, read) = flashmap::new::<u32, u32>();
+    assert!(write.synchronize_timeout(Duration::ZERO));
+
+    let (locked_tx, locked_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let reader = thread::spawn(move || {
+        let guard = read.guard();
+        locked_tx.send(()).unwrap();
+        release_rx.recv().unwrap();
+        drop(guard);
+        read
+    });
+
+    locked_rx.recv().unwrap();
+    write.guard().insert(1, 1);
+
+    // Time out repeatedly to make sure the writer can give up and resume waiting
+    assert!(!write.synchronize_timeout(Duration::ZERO));
+    assert!(!write.synchronize_timeout(Duration::from_millis(5)));
+    assert!(!write.synchronize_timeout(Duration::from_millis(5)));
+
+    release_tx.send(()).unwrap();
+    assert!(write.synchronize_timeout(Duration::from_secs(60)));
+    let read = reader.join().unwrap();
+
+    // Timing out must leave the map in a consistent state
+    let guard = read.guard();
+    write.guard().insert(2, 2);
+    assert!(!write.synchronize_timeout(Duration::from_millis(1)));
+    drop(guard);
+    write.guard().insert(3, 3);
+    assert_eq!(read.guard().len(), 3);
+}
+
+#[test]
+fn synchronize_timeout_async() {
+    use std::{
+        future::{self, Future},
+        pin::pin,
+        sync::{mpsc, Arc},
+        task::{Context, Poll, Wake},
+    };
+
+    struct ChannelWaker(std::sync::Mutex<mpsc::Sender<()>>);
+
+    impl Wake for ChannelWaker {
+        fn wake(self: Arc<Self>) {
+            let _ = self.0.lock().unwrap().send(());
+        }
+    }
+
+    let (woken_tx, woken_rx) = mpsc::channel();
+    let waker = Arc::new(ChannelWaker(std::sync::Mutex::new(woken_tx))).into();
+    let mut cx = Context::from_waker(&waker);
+
+    let (mut write, read) = flashmap::new::<u32, u32>();
+    {
+        let mut sync = pin!(write.synchronize_timeout_async(future::pending::<()>()));
+        assert_eq!(sync.as_mut().poll(&mut cx), Poll::Ready(true));
+    }
+
+    let (locked_tx, locked_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let reader = thread::spawn(move || {
+        let guard = read.guard();
+        locked_tx.send(()).unwrap();
+        release_rx.recv().unwrap();
+        drop(guard);
+        read
+    });
+
+    locked_rx.recv().unwrap();
+    write.guard().insert(1, 1);
+
+    // The timer finishing ends the wait
+    {
+        let mut sync = pin!(write.synchronize_timeout_async(future::ready(())));
+        assert_eq!(sync.as_mut().poll(&mut cx), Poll::Ready(false));
+    }
+
+    // Dropping a pending wait must leave the handle able to wait again
+    {
+        let mut sync = pin!(write.synchronize_timeout_async(future::pending::<()>()));
+        assert!(sync.as_mut().poll(&mut cx).is_pending());
+    }
+
+    {
+        let mut sync = pin!(write.synchronize_timeout_async(future::pending::<()>()));
+        assert!(sync.as_mut().poll(&mut cx).is_pending());
+        assert!(sync.as_mut().poll(&mut cx).is_pending());
+
+        // Releasing the last guard wakes the waiting task
+        release_tx.send(()).unwrap();
+        woken_rx.recv().unwrap();
+        assert_eq!(sync.as_mut().poll(&mut cx), Poll::Ready(true));
+    }
+    let read = reader.join().unwrap();
+
+    // A blocking wait must not wake the stale waker from the async wait
+    let guard = read.guard();
+    write.guard().insert(2, 2);
+    thread::scope(|s| {
+        s.spawn(move || drop(guard));
+        write.synchronize();
+    });
+    assert!(woken_rx.try_recv().is_err());
+
+    write.guard().insert(3, 3);
+    assert_eq!(read.guard().len(), 3);
+}
+
+#[test]
+fn dropped_synchronize_future_leaves_map_usable() {
+    use std::{
+        future::{self, Future},
+        pin::pin,
+        task::{Context, Waker},
+        time::Duration,
+    };
+
+    let mut cx = Context::from_waker(Waker::noop());
+    let (mut write, read) = flashmap::new::<u32, Box<u32>>();
+
+    for i in 0..3 {
+        let guard = read.guard();
+        write.guard().insert(i, Box::new(i));
+
+        // Cancel the wait while the reader is still on the old version of the map
+        {
+            let mut sync = pin!(write.synchronize_timeout_async(future::pending::<()>()));
+            assert!(sync.as_mut().poll(&mut cx).is_pending());
+        }
+        assert_eq!(guard.len(), i as usize);
+        drop(guard);
+
+        // The writer is not left waiting, and the residual count is still accurate
+        assert!(write.synchronize_timeout(Duration::ZERO));
+        write.guard().insert(i, Box::new(i + 10));
+        assert_eq!(**read.guard().get(&i).unwrap(), i + 10);
+    }
+
+    // A blocking wait after a cancelled one still waits for the guard
+    let guard = read.guard();
+    write.guard().remove(0);
+    {
+        let mut sync = pin!(write.synchronize_timeout_async(future::pending::<()>()));
+        assert!(sync.as_mut().poll(&mut cx).is_pending());
+    }
+    assert!(!write.synchronize_timeout(Duration::from_millis(10)));
+    thread::scope(|s| {
+        s.spawn(move || drop(guard));
+        write.synchronize();
+    });
+    assert_eq!(read.guard().len(), 2);
+    util::assert_both_copies_eq(&mut write, &read);
+}
+
+#[test]
+fn get_mut() {
+    let (mut write, read) = flashmap::new::<Box<u32>, Box<u32>>();
+
+    let mut guard = write.guard();
+    guard.insert(Box::new(1), Box::new(10));
+    guard.insert(Box::new(2), Box::new(20));
+    guard.publish();
+
+    let old_guard = read.guard();
+
+    let mut guard = write.guard();
+    **guard.get_mut(&1).unwrap() += 1;
+    {
+        let mut value = guard.get_mut(&1).unwrap();
+        **value *= 2;
+        assert_eq!(**value, 22);
+    }
+    // Unmodified proxies still record a replacement
+    drop(guard.get_mut(&2));
+    assert!(guard.get_mut(&3).is_none());
+    assert_eq!(**guard.get(&1).unwrap(), 22);
+    guard.publish();
+
+    assert_eq!(**old_guard.get(&1).unwrap(), 10);
+    drop(old_guard);
+
+    // The edits are replayed on the standby map before it is written to
+    let mut guard = write.guard();
+    assert_eq!(**guard.get(&1).unwrap(), 22);
+    assert_eq!(**guard.get(&2).unwrap(), 20);
+    **guard.get_mut(&2).unwrap() = 0;
+    guard.publish();
+
+    let guard = read.guard();
+    assert_eq!(**guard.get(&1).unwrap(), 22);
+    assert_eq!(**guard.get(&2).unwrap(), 0);
+}
+
+#[test]
+fn panicking_replace_leaves_map_unchanged() {
+    use std::panic::{self, AssertUnwindSafe};
+
+    let (mut write, read) = flashmap::new::<Box<u32>, Box<u32>>();
+
+    let mut guard = write.guard();
+    guard.insert(Box::new(1), Box::new(10));
+    guard.insert(Box::new(2), Box::new(20));
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        guard.replace(Box::new(1), |_| panic!("replace failed"));
+    }));
+    assert!(result.is_err());
+
+    // The guard is still usable, and the value was not replaced
+    assert_eq!(guard.len(), 2);
+    assert_eq!(**guard.get(&1).unwrap(), 10);
+    guard.replace(Box::new(2), |x| Box::new(**x + 1));
+    guard.publish();
+
+    // A guard dropped while unwinding still publishes the changes made before the panic
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut guard = write.guard();
+        guard.insert(Box::new(3), Box::new(30));
+        guard.replace(Box::new(3), |_| panic!("replace failed"));
+    }));
+    assert!(result.is_err());
+
+    // Replaying the operation log onto the standby map must not observe a bogus replacement
+    let mut guard = write.guard();
+    assert_eq!(guard.len(), 3);
+    guard.remove(Box::new(1));
+    guard.publish();
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 2);
+    assert_eq!(**guard.get(&2).unwrap(), 21);
+    assert_eq!(**guard.get(&3).unwrap(), 30);
+}
+
+#[test]
+fn custom_parker() {
+    use flashmap::WriterParker;
+    use std::{
+        sync::{mpsc, Condvar, Mutex},
+        time::Duration,
+    };
+
+    struct CondvarParker {
+        token: Mutex<bool>,
+        condvar: Condvar,
+        parked: Mutex<mpsc::Sender<()>>,
+    }
+
+    impl WriterParker for CondvarParker {
+        fn park(&self) {
+            let mut token = self.token.lock().unwrap();
+            let _ = self.parked.lock().unwrap().send(());
+            while !*token {
+                token = self.condvar.wait(token).unwrap();
+            }
+            *token = false;
+        }
+
+        fn park_timeout(&self, timeout: Duration) {
+            let token = self.token.lock().unwrap();
+            let _ = self.parked.lock().unwrap().send(());
+            let (mut token, _) = self
+                .condvar
+                .wait_timeout_while(token, timeout, |token| !*token)
+                .unwrap();
+            *token = false;
+        }
+
+        fn unpark(&self) {
+            *self.token.lock().unwrap() = true;
+            self.condvar.notify_one();
+        }
+    }
+
+    let (parked_tx, parked_rx) = mpsc::channel();
+    let parker = CondvarParker {
+        token: Mutex::new(false),
+        condvar: Condvar::new(),
+        parked: Mutex::new(parked_tx),
+    };
+    let (mut write, read) = flashmap::Builder::new()
+        .with_parker(parker)
+        .build::<u32, u32>();
+
+    let (locked_tx, locked_rx) = mpsc::channel();
+    let reader = thread::spawn(move || {
+        let guard = read.guard();
+        locked_tx.send(()).unwrap();
+        // Only release the guard once the writer is blocked on it
+        parked_rx.recv().unwrap();
+        drop(guard);
+        read
+    });
+
+    locked_rx.recv().unwrap();
+    write.guard().insert(1, 1);
+    write.guard().insert(2, 2);
+
+    let read = reader.join().unwrap();
+    assert_eq!(read.guard().len(), 2);
+
+    // Timed waits go through the parker as well
+    let guard = read.guard();
+    write.guard().insert(3, 3);
+    assert!(!write.synchronize_timeout(Duration::from_millis(10)));
+    drop(guard);
+    assert!(write.synchronize_timeout(Duration::from_millis(10)));
+}
+
+#[test]
+fn empty_guard_does_not_publish() {
+    let (mut write, read) = flashmap::new::<u32, u32>();
+    write.guard().insert(1, 1);
+
+    let guard = read.guard();
+
+    // Nothing changed, so the reader should not have to move to the other map
+    drop(write.guard());
+    write.batch().guard();
+    write.guard().publish_and_synchronize();
+    assert!(!guard.is_stale());
+    assert!(!write.last_publish_changed());
+
+    // A genuine write is still published
+    write.guard().insert(2, 2);
+    assert!(guard.is_stale());
+    assert!(write.last_publish_changed());
+    assert_eq!(guard.len(), 1);
+    drop(guard);
+
+    assert_eq!(read.guard().len(), 2);
+    write.guard().remove(1);
+    assert_eq!(read.guard().len(), 1);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "different hashes")]
+fn build_checked_catches_nondeterministic_hash() {
+    use std::hash::{Hash, Hasher};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+
+    #[derive(PartialEq, Eq)]
+    struct Unstable;
+
+    impl Hash for Unstable {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            NEXT.fetch_add(1, Ordering::Relaxed).hash(state);
+        }
+    }
+
+    // This is a lie, which is exactly what we want to catch
+    unsafe impl flashmap::TrustedHashEq for Unstable {}
+
+    let _ = flashmap::Builder::new().build_checked::<Unstable, ()>(&Unstable);
+}
+
+#[test]
+fn build_checked_allows_distinct_hashers() {
+    use std::collections::hash_map::RandomState;
+
+    let (mut write, read) = unsafe {
+        flashmap::Builder::new()
+            .with_hasher_generator(RandomState::new)
+            .build_checked::<u32, u32>(&0)
+    };
+    write.guard().insert(1, 2);
+    assert_eq!(read.guard().get(&1), Some(&2));
+}
+
+#[test]
+fn entry_ref() {
+    let (mut write, read) = flashmap::new::<String, Box<u32>>();
+
+    let mut guard = write.guard();
+    assert_eq!(**guard.entry_ref("a").or_insert_with(|| Box::new(1)), 1);
+    assert_eq!(**guard.entry_ref("a").or_insert_with(|| unreachable!()), 1);
+    guard.entry_ref("b").or_insert(Box::new(2));
+    guard.publish();
+
+    util::assert_both_copies_eq(&mut write, &read);
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 2);
+    assert_eq!(**guard.get("a").unwrap(), 1);
+    assert_eq!(**guard.get("b").unwrap(), 2);
+}
+
+#[test]
+#[cfg(feature = "serde_json")]
+fn serialization_round_trip() {
+    let (mut write, read) = flashmap::new::<u32, String>();
+
+    let mut guard = write.guard();
+    for i in 0..100 {
+        guard.insert(i, i.to_string());
+    }
+    guard.publish();
+
+    let mut buffer = Vec::new();
+    read.guard().serialize_to(&mut buffer).unwrap();
+
+    let (_write2, read2) = flashmap::deserialize_from::<_, u32, String>(&buffer[..]).unwrap();
+    assert!(read.guard() == read2.guard());
+
+    // Trailing garbage and truncated input are both errors
+    assert!(flashmap::deserialize_from::<_, u32, String>(&b"[] []"[..]).is_err());
+    assert!(flashmap::deserialize_from::<_, u32, String>(&buffer[..buffer.len() - 1]).is_err());
+}
+
+#[test]
+#[cfg(feature = "serde_json")]
+fn serialize_to() {
+    use std::{collections::HashMap, io};
+
+    let (mut write, read) = flashmap::new::<String, Vec<u32>>();
+
+    let mut buffer = Vec::new();
+    read.guard().serialize_to(&mut buffer).unwrap();
+    assert_eq!(buffer, b"[]");
+
+    let mut guard = write.guard();
+    for i in 0..50 {
+        guard.insert(i.to_string(), (0..i).collect());
+    }
+    guard.publish();
+
+    // Changes published while the guard is held do not show up in the output
+    let read_guard = read.guard();
+    let mut guard = write.guard();
+    guard.remove("0".to_owned());
+    guard.insert("new".to_owned(), vec![1]);
+    guard.publish();
+
+    buffer.clear();
+    read_guard.serialize_to(&mut buffer).unwrap();
+    let entries = serde_json::from_slice::<Vec<(String, Vec<u32>)>>(&buffer).unwrap();
+    assert_eq!(entries.len(), 50);
+    let entries = entries.into_iter().collect::<HashMap<_, _>>();
+    assert_eq!(entries.len(), 50);
+    for (key, value) in read_guard.iter() {
+        assert_eq!(&entries[key], value);
+    }
+    assert!(!entries.contains_key("new"));
+    drop(read_guard);
+
+    // Errors from the writer are passed through
+    struct Full;
+
+    impl io::Write for Full {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::StorageFull.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let error = read.guard().serialize_to(Full).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+}
+
+#[test]
+fn iter_ordered() {
+    let (mut write, read) = flashmap::Builder::new().ordered().build::<u32, Box<u32>>();
+
+    fn keys(read: &flashmap::ReadHandle<u32, Box<u32>>) -> Vec<u32> {
+        read.guard().iter_ordered().map(|(&key, _)| key).collect()
+    }
+
+    let mut guard = write.guard();
+    for i in (0..10).rev() {
+        guard.insert(i, Box::new(i));
+    }
+    guard.publish();
+    assert_eq!(keys(&read), (0..10).rev().collect::<Vec<_>>());
+
+    // Replacing keeps the position, reinserting moves to the end
+    let mut guard = write.guard();
+    guard.insert(5, Box::new(50));
+    guard.remove(9);
+    guard.remove(3);
+    guard.insert(3, Box::new(30));
+    guard.publish();
+    assert_eq!(keys(&read), [8, 7, 6, 5, 4, 2, 1, 0, 3]);
+    assert_eq!(
+        read.guard().iter_ordered().nth(3).map(|(_, value)| **value),
+        Some(50)
+    );
+
+    util::assert_both_copies_eq(&mut write, &read);
+
+    // Make sure the other copy of the order received the same changes
+    write.guard().insert(10, Box::new(10));
+    assert_eq!(keys(&read), [8, 7, 6, 5, 4, 2, 1, 0, 3, 10]);
+
+    // Enough removals to trigger compaction
+    let mut guard = write.guard();
+    for i in 100..200 {
+        guard.insert(i, Box::new(i));
+    }
+    guard.publish();
+    let mut guard = write.guard();
+    guard.extract_if(|&key, _| key >= 100 && key % 10 != 0);
+    guard.publish();
+    write.guard().insert(11, Box::new(11));
+    let expected = [8, 7, 6, 5, 4, 2, 1, 0, 3, 10]
+        .into_iter()
+        .chain((100..200).step_by(10))
+        .chain([11])
+        .collect::<Vec<_>>();
+    assert_eq!(keys(&read), expected);
+    write.guard().insert(12, Box::new(12));
+    assert_eq!(keys(&read)[..expected.len()], expected);
+
+    let mut guard = write.guard();
+    guard.clear_and_shrink(0);
+    guard.insert(1, Box::new(1));
+    guard.publish();
+    write.guard().insert(0, Box::new(0));
+    assert_eq!(keys(&read), [1, 0]);
+}
+
+#[test]
+#[should_panic(expected = "Builder::ordered")]
+fn iter_ordered_requires_ordered_map() {
+    let (_write, read) = flashmap::new::<u32, u32>();
+    let _ = read.guard().iter_ordered().count();
+}
+
+#[test]
+fn versioned_replace() {
+    let (mut write, read) = flashmap::Builder::new()
+        .versioned()
+        .build::<String, Box<u32>>();
+
+    let version = |key: &str| read.guard().get_versioned(key).map(|(_, version)| version);
+
+    let mut guard = write.guard();
+    guard.insert("a".to_owned(), Box::new(1));
+    guard.insert("b".to_owned(), Box::new(2));
+    guard.publish();
+
+    let a = version("a").unwrap();
+    let b = version("b").unwrap();
+    assert_ne!(a, b);
+
+    // Unrelated writes don't affect the version, and both copies of the map agree
+    write.guard().insert("c".to_owned(), Box::new(3));
+    write.guard().insert("d".to_owned(), Box::new(4));
+    assert_eq!(version("a"), Some(a));
+
+    // Writes made earlier through the same guard are taken into account
+    let mut guard = write.guard();
+    **guard.get_mut("b").unwrap() = 20;
+    let mismatch = guard
+        .replace_if_version("b".to_owned(), b, Box::new(200))
+        .err()
+        .unwrap();
+    assert!(mismatch.current().is_some());
+    assert_ne!(mismatch.current(), Some(b));
+    assert_eq!(
+        **guard
+            .replace_if_version("a".to_owned(), a, Box::new(10))
+            .unwrap(),
+        1
+    );
+    guard.publish();
+
+    let (value, b) = read
+        .guard()
+        .get_versioned("b")
+        .map(|(value, version)| (**value, version))
+        .unwrap();
+    assert_eq!(value, 20);
+    assert_eq!(version("b"), Some(b));
+    assert_ne!(version("a"), Some(a));
+
+    // Removing and reinserting a key produces a new version
+    let mut guard = write.guard();
+    guard.remove("b".to_owned());
+    assert_eq!(
+        guard
+            .replace_if_version("b".to_owned(), b, Box::new(0))
+            .err()
+            .unwrap()
+            .current(),
+        None
+    );
+    guard.insert("b".to_owned(), Box::new(2));
+    guard.publish();
+    assert_ne!(version("b"), Some(b));
+
+    write.guard().extract_if(|key, _| key == "c");
+    write.guard().insert("e".to_owned(), Box::new(5));
+    assert_eq!(version("c"), None);
+    assert!(version("e").is_some());
+
+    let mut guard = write.guard();
+    guard.clear_and_shrink(0);
+    guard.insert("a".to_owned(), Box::new(1));
+    guard.publish();
+    write.guard().insert("f".to_owned(), Box::new(6));
+    assert!(version("a").is_some());
+    assert_eq!(version("b"), None);
+    assert_eq!(read.guard().len(), 2);
+}
+
+#[test]
+fn borrowed_lookup_of_shared_str_keys() {
+    use std::{borrow::Cow, sync::Arc};
+
+    let (mut write, read) = flashmap::new::<Arc<str>, u32>();
+    write.guard().insert(Arc::from("ferris"), 1);
+    assert_eq!(read.guard().get("ferris"), Some(&1));
+    assert!(write.contains_key("ferris"));
+    assert_eq!(*write.guard().remove(Arc::from("ferris")).unwrap(), 1);
+
+    let (mut write, read) = flashmap::new::<Box<str>, u32>();
+    write.guard().insert(Box::from("corro"), 2);
+    assert_eq!(read.guard().get("corro"), Some(&2));
+
+    let (mut write, read) = flashmap::new::<Arc<[u8]>, u32>();
+    write.guard().insert(Arc::from(&b"bytes"[..]), 3);
+    assert_eq!(read.guard().get(&b"bytes"[..]), Some(&3));
+
+    let (mut write, read) = flashmap::new::<Cow<'static, str>, u32>();
+    write.guard().insert(Cow::Owned("owned".to_owned()), 4);
+    assert_eq!(read.guard().get("owned"), Some(&4));
+}
+
+#[test]
+fn trusted_hash_eq_macro() {
+    #[derive(PartialEq, Eq, Hash, Debug)]
+    struct UserId(u64);
+
+    #[derive(PartialEq, Eq, Hash, Debug)]
+    struct GroupId(u32);
+
+    #[derive(PartialEq, Eq, Hash, Debug)]
+    struct Tagged<T>(&'static str, T);
+
+    #[derive(PartialEq, Eq, Hash, Debug)]
+    struct Pair<A, B>(A, B);
+
+    flashmap::trusted_hash_eq! {
+        // Safety: the derived implementations only hash and compare types which implement
+        // TrustedHashEq
+        unsafe UserId, GroupId, {T} Tagged<T>, {A, B,} Pair<A, B>,
+    }
+
+    flashmap::assert_trusted_hash_eq::<Tagged<Pair<UserId, GroupId>>>();
+    flashmap::assert_trusted_hash_eq::<Pair<String, Tagged<u8>>>();
+
+    let (mut write, read) = flashmap::new::<Tagged<Pair<UserId, GroupId>>, GroupId>();
+    write
+        .guard()
+        .insert(Tagged("member", Pair(UserId(1), GroupId(2))), GroupId(2));
+    assert_eq!(
+        read.guard()
+            .get(&Tagged("member", Pair(UserId(1), GroupId(2)))),
+        Some(&GroupId(2))
+    );
+    assert!(!read
+        .guard()
+        .contains_key(&Tagged("member", Pair(UserId(2), GroupId(2)))));
+}
+
+#[test]
+fn apply_ops() {
+    use flashmap::MapOp;
+
+    let (mut write, read) = flashmap::new::<u32, Box<u32>>();
+
+    write
+        .guard()
+        .apply((0..10).map(|i| MapOp::Insert(i, Box::new(i))));
+    write.guard().apply([
+        MapOp::Remove(0),
+        MapOp::Remove(0),
+        MapOp::Replace(1, Box::new(10)),
+        MapOp::Replace(20, Box::new(20)),
+        MapOp::Insert(2, Box::new(20)),
+    ]);
+
+    util::assert_both_copies_eq(&mut write, &read);
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 9);
+    assert!(!guard.contains_key(&0));
+    assert!(!guard.contains_key(&20));
+    assert_eq!(**guard.get(&1).unwrap(), 10);
+    assert_eq!(**guard.get(&2).unwrap(), 20);
+}
+
+#[test]
+fn op_observer() {
+    use flashmap::MapOp;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log2 = Arc::clone(&log);
+    let (mut write, read) = flashmap::new::<u32, Box<u32>>();
+    write.set_op_observer(move |op| {
+        let op = match *op {
+            MapOp::Insert(&key, value) => MapOp::Insert(key, value.clone()),
+            MapOp::Replace(&key, value) => MapOp::Replace(key, value.clone()),
+            MapOp::Remove(&key) => MapOp::Remove(key),
+            MapOp::Clear => MapOp::Clear,
+            _ => unreachable!(),
+        };
+        log2.lock().unwrap().push(op);
+    });
+
+    let mut guard = write.guard();
+    for i in 0..10 {
+        guard.insert(i, Box::new(i));
+    }
+    guard.replace(1, |value| Box::new(**value + 10));
+    guard.replace(20, |value| Box::new(**value + 10));
+    guard.remove(2);
+    guard.remove(20);
+    // Changes are only reported once they are published
+    assert!(log.lock().unwrap().is_empty());
+    guard.publish();
+    assert_eq!(log.lock().unwrap().len(), 12);
+
+    // Publishing without changes reports nothing
+    write.guard();
+    assert_eq!(log.lock().unwrap().len(), 12);
+
+    write.guard().clear_and_shrink(0);
+    write.replace_all(
+        (0..5)
+            .map(|i| (i, Box::new(i * 2)))
+            .collect::<HashMap<_, _>>(),
+    );
+    write.guard().insert(5, Box::new(10));
+
+    // Replaying the log reproduces the map
+    let (mut replica, replica_read) = flashmap::new::<u32, Box<u32>>();
+    replica.guard().apply(log.lock().unwrap().drain(..));
+
+    let expected = read
+        .guard()
+        .iter()
+        .map(|(&key, value)| (key, **value))
+        .collect::<HashMap<_, _>>();
+    let replayed = replica_read
+        .guard()
+        .iter()
+        .map(|(&key, value)| (key, **value))
+        .collect::<HashMap<_, _>>();
+    assert_eq!(expected.len(), 6);
+    assert_eq!(expected, replayed);
+}
+
+#[test]
+fn prealloc() {
+    let (mut write, read) = flashmap::Builder::new()
+        .prealloc(500)
+        .build::<Box<u32>, Box<u32>>();
+
+    // The first guard of each handle sees a different copy of the map
+    assert!(read.guard().capacity() >= 500);
+    assert!(write.guard().capacity() >= 500);
+
+    let mut guard = write.guard();
+    let capacity = guard.capacity();
+    for i in 0..500 {
+        guard.insert(Box::new(i), Box::new(i));
+    }
+    assert_eq!(guard.capacity(), capacity);
+    guard.publish();
+
+    write.guard().remove(Box::new(0));
+    assert_eq!(read.guard().len(), 499);
+    assert_eq!(**read.guard().get(&499).unwrap(), 499);
+
+    // Preallocating nothing is fine as well
+    let (mut write, read) = flashmap::Builder::new().prealloc(0).build::<u32, u32>();
+    write.guard().insert(1, 1);
+    assert_eq!(read.guard().len(), 1);
+}
+
+// Counts the allocations made by each thread, so that tests can check that an operation did not
+// allocate regardless of what other tests are running concurrently
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+fn allocations_during<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn reserve_ops() {
+    const COUNT: u32 = 1000;
+
+    let (mut write, read) = flashmap::with_capacity::<u32, u32>(COUNT as usize);
+    let mut guard = write.guard();
+    guard.reserve_ops(COUNT as usize);
+    let allocations = allocations_during(|| {
+        for i in 0..COUNT {
+            guard.insert(i, i);
+        }
+    });
+    assert_eq!(allocations, 0);
+    guard.publish();
+    assert_eq!(read.guard().len(), COUNT as usize);
+
+    // Without reserving, recording the same changes has to grow the log
+    let (mut write, _read) = flashmap::with_capacity::<u32, u32>(COUNT as usize);
+    let mut guard = write.guard();
+    let allocations = allocations_during(|| {
+        for i in 0..COUNT {
+            guard.insert(i, i);
+        }
+    });
+    assert!(allocations > 0);
+}
+
+#[test]
+fn evicted_key() {
+    use flashmap::Evicted;
+
+    let (mut write, _read) = flashmap::new::<String, String>();
+
+    let mut guard = write.guard();
+    for i in 0..10 {
+        guard.insert(i.to_string(), i.to_string());
+    }
+    guard.publish();
+
+    let mut guard = write.guard();
+    let replaced = guard.insert("1".to_owned(), "one".to_owned()).unwrap();
+    let mapped = guard.replace("2".to_owned(), |_| "two".to_owned()).unwrap();
+    let removed = guard.remove("3".to_owned()).unwrap();
+    let key = Evicted::key(&removed);
+
+    // Recording more operations must not invalidate the keys
+    for i in 10..100 {
+        guard.insert(i.to_string(), i.to_string());
+    }
+    let extracted = guard.extract_if(|key, _| key == "4");
+
+    assert_eq!(Evicted::key(&replaced), "1");
+    assert_eq!(Evicted::key(&mapped), "2");
+    assert_eq!(key, "3");
+    assert_eq!(Evicted::key(&extracted[0].1), "4");
+    assert_eq!(*extracted[0].1, "4");
+}
+
+#[test]
+fn retain_map() {
+    use std::sync::Arc;
+
+    let (mut write, read) = flashmap::new::<Box<u32>, Arc<u32>>();
+    let values = (0..10).map(Arc::new).collect::<Vec<_>>();
+
+    let mut guard = write.guard();
+    for (i, value) in values.iter().enumerate() {
+        guard.insert(Box::new(i as u32), Arc::clone(value));
+    }
+    guard.publish();
+
+    write.guard().retain_map(|key, value| match **key % 3 {
+        0 => None,
+        1 => Some(Arc::new(**value * 10)),
+        _ => Some(Arc::clone(value)),
+    });
+
+    util::assert_both_copies_eq(&mut write, &read);
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 6);
+    assert!(!guard.contains_key(&0));
+    assert_eq!(**guard.get(&1).unwrap(), 10);
+    assert_eq!(**guard.get(&2).unwrap(), 2);
+    drop(guard);
+
+    // Removed and replaced values were dropped, retained clones are still in the map
+    for (i, value) in values.iter().enumerate() {
+        let expected = if i % 3 == 2 { 2 } else { 1 };
+        assert_eq!(Arc::strong_count(value), expected);
+    }
+}
+
+#[test]
+fn repeated_empty_publishes() {
+    use std::time::Duration;
+
+    let (mut write, read) = flashmap::new::<u32, u32>();
+    write.guard().insert(1, 1);
+
+    let guard = read.guard();
+    write.guard().publish();
+    write.guard().publish();
+    write.batch().publish();
+
+    // No maps were swapped, so the writer isn't waiting on the reader
+    assert!(write.synchronize_timeout(Duration::ZERO));
+    assert!(write.try_reclaim());
+    assert!(!guard.is_stale());
+    assert_eq!(guard.len(), 1);
+}
+
+#[test]
+#[cfg(feature = "diagnostics")]
+fn maps_converge_after_two_publishes() {
+    let (mut write, read) = flashmap::new::<u32, Box<u32>>();
+
+    let mut guard = write.guard();
+    guard.insert(1, Box::new(1));
+    guard.insert(2, Box::new(2));
+    guard.publish();
+
+    // Readers see the change right away, but the other copy hasn't been caught up yet
+    assert_eq!(**read.guard().get(&1).unwrap(), 1);
+    assert!(!write.maps_converged());
+
+    let mut guard = write.guard();
+    guard.remove(2);
+    guard.insert(3, Box::new(3));
+    guard.insert(1, Box::new(10));
+    guard.publish();
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 2);
+    assert_eq!(**guard.get(&1).unwrap(), 10);
+    drop(guard);
+    assert!(!write.maps_converged());
+
+    // An empty guard still replays the operation log without publishing anything
+    write.guard().publish();
+    assert!(write.maps_converged());
+    assert!(!write.last_publish_changed());
+}
+
+#[test]
+fn get_or_insert_with_key() {
+    let (mut write, read) = flashmap::new::<String, Box<usize>>();
+
+    let mut guard = write.guard();
+    assert_eq!(
+        **guard.get_or_insert_with_key("a".to_owned(), |key| Box::new(key.len())),
+        1
+    );
+    guard.insert("bc".to_owned(), Box::new(10));
+    assert_eq!(
+        **guard.get_or_insert_with_key("bc".to_owned(), |_| unreachable!()),
+        10
+    );
+    guard.publish();
+
+    util::assert_both_copies_eq(&mut write, &read);
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 2);
+    assert_eq!(**guard.get("a").unwrap(), 1);
+    assert_eq!(**guard.get("bc").unwrap(), 10);
+}
+
+#[test]
+fn single_reader() {
+    use std::panic::{self, AssertUnwindSafe};
+
+    let (mut write, read) = flashmap::Builder::new()
+        .single_reader()
+        .build::<u32, Box<u32>>();
+
+    let guard = read.guard();
+    write.guard().insert(1, Box::new(1));
+    assert_eq!(write.open_guards(), 1);
+    assert!(!write.synchronize_timeout(std::time::Duration::ZERO));
+    drop(guard);
+    assert!(write.try_reclaim());
+
+    write.guard().insert(2, Box::new(2));
+    assert_eq!(read.guard().len(), 2);
+
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| read.clone())).is_err());
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| read.guard_owned())).is_err());
+    assert!(read.clone_many(0).is_empty());
+
+    // The writer keeps working after the only reader is gone
+    drop(read);
+    write.guard().insert(3, Box::new(3));
+    write.guard().remove(1);
+    assert_eq!(write.peek(|view| view.len()), 2);
+}
+
+#[test]
+fn clone_many() {
+    let (mut write, read) = flashmap::new::<u32, u32>();
+    assert!(read.clone_many(0).is_empty());
+
+    let readers = read.clone_many(4);
+    assert_eq!(readers.len(), 4);
+    write.guard().insert(1, 2);
+
+    // Each clone is registered separately, so the writer waits for guards held through any of them
+    let old = readers[0].guard();
+    write.guard().insert(1, 3);
+    assert_eq!(old.get(&1), Some(&2));
+    assert_eq!(readers[1].guard().get(&1), Some(&3));
+    drop(old);
+
+    thread::scope(|s| {
+        for reader in &readers {
+            s.spawn(move || assert_eq!(reader.guard().get(&1), Some(&3)));
+        }
+    });
+
+    drop(readers);
+    write.guard().insert(1, 4);
+    assert_eq!(read.guard().get(&1), Some(&4));
+}
+
+#[test]
+fn replay_mixed_operations_after_inserts() {
+    let (mut write, read) = flashmap::new::<Box<u32>, Box<u32>>();
+
+    // A log which only contains inserts
+    let mut guard = write.guard();
+    for i in 0..50 {
+        guard.insert(Box::new(i), Box::new(i));
+    }
+    guard.publish();
+    util::assert_both_copies_eq(&mut write, &read);
+
+    // A log which starts with inserts, followed by every other kind of change
+    let mut guard = write.guard();
+    for i in 50..100 {
+        guard.insert(Box::new(i), Box::new(i));
+    }
+    guard.insert(Box::new(0), Box::new(1000));
+    guard.remove(Box::new(1));
+    guard.replace(Box::new(2), |value| Box::new(**value + 1000));
+    guard.retain_map(|key, value| (**key != 60).then(|| value.clone()));
+    guard.publish();
+    util::assert_both_copies_eq(&mut write, &read);
+
+    // Insert-only again, after the log was flushed
+    let mut guard = write.guard();
+    guard.insert(Box::new(100), Box::new(100));
+    guard.publish();
+    util::assert_both_copies_eq(&mut write, &read);
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 99);
+    assert_eq!(**guard.get(&0).unwrap(), 1000);
+    assert!(!guard.contains_key(&1));
+    assert_eq!(**guard.get(&2).unwrap(), 1002);
+    assert!(!guard.contains_key(&60));
+}
+
+#[test]
+fn replace_all() {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    let (mut write, read) = flashmap::new::<u32, Arc<u32>>();
+    let old = Arc::new(0);
+
+    let mut guard = write.guard();
+    for i in 0..100 {
+        guard.insert(i, Arc::clone(&old));
+    }
+    guard.publish();
+
+    let new = Arc::new(1);
+    let reloaded = (50..60)
+        .map(|i| (i, Arc::clone(&new)))
+        .collect::<HashMap<_, _>>();
+    write.replace_all(reloaded);
+    assert!(write.last_publish_changed());
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 10);
+    assert!(guard
+        .iter()
+        .all(|(key, value)| (50..60).contains(key) && **value == 1));
+    drop(guard);
+
+    // The old entries are dropped once both copies of the map caught up
+    write.flush();
+    assert_eq!(Arc::strong_count(&old), 1);
+    assert_eq!(Arc::strong_count(&new), 11);
+    assert!(write.guard().capacity() < 100);
+
+    // Both copies of the map can still be modified after the reset
+    let mut guard = write.guard();
+    guard.remove(50);
+    guard.insert(1, Arc::clone(&new));
+    guard.publish();
+    write.guard().remove(51);
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 9);
+    assert!(!guard.contains_key(&50) && !guard.contains_key(&51));
+    drop(guard);
+
+    write.flush();
+    assert_eq!(Arc::strong_count(&new), 10);
+}
+
+#[test]
+fn replace_all_with_distinct_hashers() {
+    use std::collections::{hash_map::RandomState, HashMap};
+
+    // Each copy of the map gets its own randomly keyed hasher
+    let (mut write, read) = unsafe {
+        flashmap::Builder::new()
+            .with_hasher_generator(RandomState::new)
+            .build::<u32, Box<u32>>()
+    };
+
+    write.replace_all((0..64).map(|i| (i, Box::new(i))).collect::<HashMap<_, _>>());
+    write.guard().insert(64, Box::new(64));
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 65);
+    assert!((0..65).all(|i| **guard.get(&i).unwrap() == i));
+    drop(guard);
+
+    // Replaying changes to existing keys looks them up in the other copy
+    let mut guard = write.guard();
+    guard.replace(1, |_| Box::new(100));
+    guard.remove(2);
+    guard.publish();
+    write.guard().remove(3);
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 63);
+    assert_eq!(**guard.get(&1).unwrap(), 100);
+    drop(guard);
+
+    util::assert_both_copies_eq(&mut write, &read);
+}
+
+#[test]
+fn panicking_hash_in_replace_all_leaves_map_unchanged() {
+    use std::{
+        cell::Cell,
+        collections::HashMap,
+        hash::{Hash, Hasher},
+        panic::{self, AssertUnwindSafe},
+    };
+
+    thread_local! {
+        static PANIC_ON_HASH: Cell<bool> = const { Cell::new(false) };
+    }
+
+    #[derive(PartialEq, Eq)]
+    struct Key(u32);
+
+    impl Hash for Key {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            if self.0 == 5 && PANIC_ON_HASH.with(Cell::get) {
+                panic!("hash failed");
+            }
+            self.0.hash(state);
+        }
+    }
+
+    flashmap::trusted_hash_eq! {
+        // Safety: the hash only depends on the key, aside from panicking
+        unsafe Key
+    }
+
+    let (mut write, read) = flashmap::new::<Key, Box<u32>>();
+
+    let mut guard = write.guard();
+    for i in 0..3 {
+        guard.insert(Key(i), Box::new(i));
+    }
+    guard.publish();
+
+    let reloaded = (0..10)
+        .map(|i| (Key(i), Box::new(i * 10)))
+        .collect::<HashMap<_, _>>();
+    PANIC_ON_HASH.with(|panic| panic.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| write.replace_all(reloaded)));
+    PANIC_ON_HASH.with(|panic| panic.set(false));
+    assert!(result.is_err());
+    assert!(!write.last_publish_changed());
+
+    let mut guard = write.guard();
+    guard.remove(Key(0));
+    guard.insert(Key(1), Box::new(10));
+    guard.publish();
+    util::assert_both_copies_eq(&mut write, &read);
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 2);
+    assert_eq!(**guard.get(&Key(1)).unwrap(), 10);
+    assert_eq!(**guard.get(&Key(2)).unwrap(), 2);
+}
+
+#[test]
+fn replace_all_ordered_and_versioned() {
+    use std::collections::HashMap;
+
+    let (mut write, read) = flashmap::Builder::new()
+        .ordered()
+        .versioned()
+        .build::<u32, Box<u32>>();
+
+    write.guard().insert(0, Box::new(0));
+    let (_, old_version) = read
+        .guard()
+        .get_versioned(&0)
+        .map(|(_, v)| ((), v))
+        .unwrap();
+
+    write.replace_all((1..=3).map(|i| (i, Box::new(i))).collect::<HashMap<_, _>>());
+    write.guard().insert(4, Box::new(4));
+
+    let guard = read.guard();
+    let keys = guard
+        .iter_ordered()
+        .map(|(&key, _)| key)
+        .collect::<Vec<_>>();
+    assert_eq!(keys.len(), 4);
+    assert_eq!(keys[3], 4);
+
+    let mut versions = (1..=4)
+        .map(|key| guard.get_versioned(&key).unwrap().1)
+        .collect::<Vec<_>>();
+    assert!(versions.iter().all(|&version| version > old_version));
+    versions.sort_unstable();
+    versions.dedup();
+    assert_eq!(versions.len(), 4);
+    assert!(guard.get_versioned(&0).is_none());
+}
+
+#[test]
+fn into_hash_map() {
+    use std::sync::Arc;
+
+    let (mut write, read) = flashmap::new::<u32, Arc<u32>>();
+    let value = Arc::new(0);
+
+    let mut guard = write.guard();
+    for i in 0..10 {
+        guard.insert(i, Arc::clone(&value));
+    }
+    guard.publish();
+
+    // Unpublished changes are flushed into the returned map
+    let mut guard = write.guard();
+    guard.remove(0);
+    guard.insert(10, Arc::clone(&value));
+    drop(guard);
+
+    let owned = read.guard_owned();
+    drop(read);
+    let write = write.into_hash_map().unwrap_err();
+    drop(owned);
+
+    let map = write.into_hash_map().ok().expect("no readers remain");
+    assert_eq!(map.len(), 10);
+    assert!((1..=10).all(|key| map.contains_key(&key)));
+    assert_eq!(Arc::strong_count(&value), 11);
+
+    drop(map);
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+#[test]
+fn drain() {
+    use std::sync::Arc;
+
+    let (mut write, read) = flashmap::Builder::new().ordered().build::<u32, Arc<u32>>();
+    let value = Arc::new(0);
+
+    let mut guard = write.guard();
+    for i in 0..10 {
+        guard.insert(i, Arc::clone(&value));
+    }
+    guard.publish();
+    write.guard().remove(0);
+
+    let mut drained = write.drain().map(|(key, _)| key).collect::<Vec<_>>();
+    drained.sort_unstable();
+    assert_eq!(drained, (1..10).collect::<Vec<_>>());
+    assert_eq!(Arc::strong_count(&value), 1);
+
+    assert!(read.guard().is_empty());
+    assert!(write.drain().next().is_none());
+
+    // The handle is still usable, and the drained entries are not dropped again
+    write.guard().insert(20, Arc::clone(&value));
+    write.guard().insert(21, Arc::clone(&value));
+    let guard = read.guard();
+    assert_eq!(
+        guard
+            .iter_ordered()
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>(),
+        [20, 21]
+    );
+    drop(guard);
+    assert_eq!(Arc::strong_count(&value), 3);
+
+    drop((write, read));
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+#[test]
+fn remove_many() {
+    let (mut write, read) = flashmap::new::<u32, Box<u32>>();
+
+    let mut guard = write.guard();
+    for i in 0..10 {
+        guard.insert(i, Box::new(i));
+    }
+    guard.publish();
+
+    let mut guard = write.guard();
+    // Absent and repeated keys are not counted
+    assert_eq!(guard.remove_many([2, 4, 4, 20]), 2);
+    assert_eq!(guard.remove_many(Vec::new()), 0);
+    assert_eq!(guard.len(), 8);
+    assert_eq!(read.guard().len(), 10);
+    guard.publish();
+
+    let guard = read.guard();
+    assert_eq!(guard.len(), 8);
+    assert!(!guard.contains_key(&2));
+    assert!(!guard.contains_key(&4));
+    assert_eq!(**guard.get(&3).unwrap(), 3);
+    drop(guard);
+
+    util::assert_both_copies_eq(&mut write, &read);
+}
+
+#[test]
+fn set_drops_evicted_values() {
+    use std::sync::Arc;
+
+    let (mut write, read) = flashmap::new::<u32, Arc<()>>();
+    let old = Arc::new(());
+    let new = Arc::new(());
+
+    write.guard().set(1, Arc::clone(&old));
+    write.guard().set(1, Arc::clone(&new));
+    assert!(Arc::ptr_eq(read.guard().get(&1).unwrap(), &new));
+
+    write.flush();
+    assert_eq!(Arc::strong_count(&old), 1);
+    assert_eq!(Arc::strong_count(&new), 2);
+}
+
+#[test]
+fn insert_unique_unchecked() {
+    let (mut write, read) = flashmap::Builder::new().ordered().build::<u32, Box<u32>>();
+
+    let mut guard = write.guard();
+    for i in 0..10 {
+        unsafe { guard.insert_unique_unchecked(i, Box::new(i)) };
+    }
+    guard.publish();
+
+    let mut guard = write.guard();
+    guard.remove(3);
+    guard.replace(4, |value| Box::new(**value + 10));
+    guard.publish();
+
+    // Both copies of the map agree after the changes are replayed
+    for _ in 0..2 {
+        write.guard();
+        let guard = read.guard();
+        assert_eq!(guard.len(), 9);
+        assert_eq!(guard.get(&3), None);
+        assert_eq!(guard.get(&4).map(|value| **value), Some(14));
+        assert_eq!(
+            guard
+                .iter_ordered()
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>(),
+            [0, 1, 2, 4, 5, 6, 7, 8, 9]
+        );
+    }
+}
+
+#[test]
+fn insert_and_get() {
+    let (mut write, read) = flashmap::new::<u32, Box<u32>>();
+
+    let mut guard = write.guard();
+    let (value, evicted) = guard.insert_and_get(1, Box::new(10));
+    assert_eq!(**value, 10);
+    assert!(evicted.is_none());
+    guard.publish();
+
+    let mut guard = write.guard();
+    let (value, evicted) = guard.insert_and_get(1, Box::new(20));
+    assert_eq!(**value, 20);
+    let leaked = flashmap::Evicted::leak(evicted.unwrap());
+    guard.publish();
+
+    assert_eq!(**read.guard().get(&1).unwrap(), 20);
+    assert_eq!(*write.reclaim_one(leaked), 10);
+}
+
+#[test]
+fn ignored_evicted_values_are_dropped_once() {
+    use std::sync::{Arc, Mutex};
+
+    // Records the ID of every dropped value, so that double drops can be detected
+    struct CountDrop(u32, Arc<Mutex<Vec<u32>>>);
+
+    impl Drop for CountDrop {
+        fn drop(&mut self) {
+            self.1.lock().unwrap().push(self.0);
+        }
+    }
+
+    let drops = Arc::new(Mutex::new(Vec::new()));
+    let value = |id| CountDrop(id, Arc::clone(&drops));
+    let dropped = || {
+        let mut dropped = drops.lock().unwrap().clone();
+        dropped.sort_unstable();
+        dropped
+    };
+
+    let (mut write, read) = flashmap::new::<u32, CountDrop>();
+
+    let mut guard = write.guard();
+    guard.insert(1, value(1));
+    guard.insert(2, value(2));
+    guard.insert(3, value(3));
+    guard.publish();
+
+    // Ignore the evicted values
+    let mut guard = write.guard();
+    guard.insert(1, value(10));
+    guard.replace(2, |_| value(20));
+    guard.remove(3);
+    guard.publish();
+    assert!(dropped().is_empty());
+
+    // The evicted values are dropped once the other map catches up, and only then
+    write.flush();
+    assert_eq!(dropped(), [1, 2, 3]);
+    write.flush();
+    write.guard().publish();
+    assert_eq!(dropped(), [1, 2, 3]);
+
+    // Evicted values which were inspected behave the same way
+    let mut guard = write.guard();
+    let first = guard.insert(1, value(100)).unwrap();
+    let second = guard.replace(2, |_| value(200)).unwrap();
+    assert_eq!((first.0, second.0), (10, 20));
+    guard.publish();
+    write.flush();
+    assert_eq!(dropped(), [1, 2, 3, 10, 20]);
+
+    drop(write);
+    drop(read);
+    assert_eq!(dropped(), [1, 2, 3, 10, 20, 100, 200]);
+}
+
+#[test]
+fn cached_guard() {
+    let (mut write, read) = flashmap::new::<u32, Box<u32>>();
+    write.guard().insert(1, Box::new(1));
+
+    let mut guard = read.guard_cached();
+    assert!(!guard.refresh());
+    assert_eq!(write.open_guards(), 1);
+
+    write.guard().insert(2, Box::new(2));
+    assert_eq!(guard.len(), 1);
+
+    // The cached guard pins the old version until it is refreshed
+    assert!(!write.try_reclaim());
+    assert!(guard.refresh());
+    assert!(write.try_reclaim());
+    assert_eq!(guard.len(), 2);
+    assert_eq!(write.open_guards(), 1);
+
+    drop(guard);
+    assert_eq!(write.open_guards(), 0);
+}
+
+#[test]
+fn eager_reclaim() {
+    use flashmap::{Builder, Evicted};
+    use std::sync::Arc;
+
+    let (mut write, read) = Builder::new().eager_reclaim().build::<u32, Arc<u32>>();
+    let values = (0..6).map(Arc::new).collect::<Vec<_>>();
+    let count = |index: usize| Arc::strong_count(&values[index]) - 1;
+
+    let mut guard = write.guard();
+    for (key, value) in values[..4].iter().enumerate() {
+        guard.insert(key as u32, Arc::clone(value));
+    }
+    drop(guard);
+    write.flush();
+
+    let read_guard = read.guard();
+    let mut guard = write.guard();
+    // Replaced twice, so the value inserted in between is evicted as well
+    guard.insert(0, Arc::clone(&values[4]));
+    guard.insert(0, Arc::clone(&values[5]));
+    guard.remove(1);
+    let leaked = Evicted::leak(guard.remove(2).unwrap());
+    drop(guard);
+
+    // The evicted values may still be visible to the reader, so none of them are dropped yet
+    assert_eq!((0..6).map(count).collect::<Vec<_>>(), [1; 6]);
+    assert_eq!(**read_guard.get(&0).unwrap(), 0);
+
+    // The last reader to move off of the old map drops them, without any action from the writer
+    drop(read_guard);
+    assert_eq!((0..6).map(count).collect::<Vec<_>>(), [0, 0, 1, 1, 0, 1]);
+    assert_eq!(*write.reclaim_one(leaked), 2);
+
+    // With no reader viewing the old map, publishing drops the evicted values right away
+    write.guard().remove(3);
+    assert_eq!(count(3), 0);
+
+    // Values removed by clearing the map are dropped by the writer as usual
+    let read_guard = read.guard();
+    write.guard().clear_and_shrink(0);
+    drop(read_guard);
+    assert_eq!(count(5), 1);
+    write.flush();
+    assert_eq!(count(5), 0);
+
+    write.guard().insert(1, Arc::clone(&values[1]));
+    util::assert_both_copies_eq(&mut write, &read);
+    assert_eq!(count(1), 1);
+}
+
+#[test]
+fn try_replace() {
+    let (mut write, read) = flashmap::new::<u32, Box<u32>>();
+    write.guard().insert(1, Box::new(1));
+
+    // A failed replacement records nothing, so there is nothing to publish
+    let mut guard = write.guard();
+    assert_eq!(
+        guard.try_replace(1, |_| Err("failed")).err(),
+        Some("failed")
+    );
+    guard.publish();
+    assert!(!write.last_publish_changed());
+
+    let mut guard = write.guard();
+    let evicted = guard
+        .try_replace(1, |old| Ok::<_, ()>(Box::new(**old + 1)))
+        .unwrap()
+        .unwrap();
+    assert_eq!(**evicted, 1);
+    guard.publish();
+    assert!(write.last_publish_changed());
+
+    // Make sure both copies of the map received the replacement
+    write.guard().insert(2, Box::new(2));
+    assert_eq!(**read.guard().get(&1).unwrap(), 2);
+}
+
+#[test]
+fn get_or_try_insert_with() {
+    let (mut write, read) = flashmap::new::<u32, Box<u32>>();
+
+    // A failed insertion records nothing, so there is nothing to publish
+    let mut guard = write.guard();
+    assert!(guard.get_or_try_insert_with(1, || Err(())).is_err());
+    guard.publish();
+    assert!(!write.last_publish_changed());
+
+    let mut guard = write.guard();
+    assert_eq!(
+        **guard
+            .get_or_try_insert_with(1, || Ok::<_, ()>(Box::new(1)))
+            .unwrap(),
+        1
+    );
+    assert_eq!(**guard.get_or_try_insert_with(1, || Err(())).unwrap(), 1);
+    guard.publish();
+
+    // Make sure both copies of the map received the insertion
+    write.guard().insert(2, Box::new(2));
+    assert_eq!(**read.guard().get(&1).unwrap(), 1);
+}
@@ -70,6 +70,94 @@ pub fn reader_and_writer() {
     });
 }
 
+#[test]
+pub fn unpadded_reader_and_writer() {
+    util::maybe_loom_model(|| {
+        let (mut write, read) = flashmap::Builder::new()
+            .without_cache_padding()
+            .build::<TrackAccess<u32>, TrackAccess<u32>>();
+
+        let t1 = thread::spawn(move || {
+            write
+                .guard()
+                .insert(TrackAccess::new(10), TrackAccess::new(20));
+            write
+                .guard()
+                .replace(TrackAccess::new(10), |x| TrackAccess::new(*x.get() + 1));
+        });
+
+        let t2 = thread::spawn(move || {
+            let res = read.guard().get(&10).map(|x| *x.get());
+            assert!(matches!(res, Some(20) | Some(21) | None));
+            read
+        });
+
+        t1.join().unwrap();
+        let read = t2.join().unwrap();
+
+        assert_eq!(read.guard().get(&10).map(|x| *x.get()).unwrap(), 21);
+    });
+}
+
+#[test]
+pub fn single_reader_and_writer() {
+    util::maybe_loom_model(|| {
+        let (mut write, read) = flashmap::Builder::new()
+            .single_reader()
+            .build::<TrackAccess<u32>, TrackAccess<u32>>();
+
+        let t1 = thread::spawn(move || {
+            write
+                .guard()
+                .insert(TrackAccess::new(10), TrackAccess::new(20));
+            write
+                .guard()
+                .replace(TrackAccess::new(10), |x| TrackAccess::new(*x.get() + 1));
+            write
+        });
+
+        let t2 = thread::spawn(move || {
+            let res = read.guard().get(&10).map(|x| *x.get());
+            assert!(matches!(res, Some(20) | Some(21) | None));
+            drop(read);
+        });
+
+        let write = t1.join().unwrap();
+        t2.join().unwrap();
+
+        assert_eq!(write.peek(|view| view.get(&10).map(|x| *x.get())), Some(21));
+    });
+}
+
+#[test]
+pub fn synchronize_timeout_races_reader() {
+    util::maybe_loom_model(|| {
+        let (mut write, read) = flashmap::new::<TrackAccess<u32>, TrackAccess<u32>>();
+
+        let guard = read.guard_owned();
+
+        let t1 = thread::spawn(move || {
+            write
+                .guard()
+                .insert(TrackAccess::new(10), TrackAccess::new(20));
+            // A zero timeout immediately gives up, racing with the reader releasing its guard
+            let _ = write.synchronize_timeout(std::time::Duration::ZERO);
+            write
+                .guard()
+                .replace(TrackAccess::new(10), |x| TrackAccess::new(*x.get() + 1));
+        });
+
+        let t2 = thread::spawn(move || {
+            assert!(guard.get(&10).is_none());
+            drop(guard);
+        });
+
+        t1.join().unwrap();
+        t2.join().unwrap();
+        assert_eq!(read.guard().get(&10).map(|x| *x.get()), Some(21));
+    });
+}
+
 #[test]
 pub fn many_writes() {
     util::maybe_loom_model(|| {
@@ -261,6 +349,72 @@ pub fn evicted_and_leaked_values() {
     });
 }
 
+#[test]
+pub fn iterate_during_publishes() {
+    util::maybe_loom_model(|| {
+        let (mut write, read) = flashmap::new::<TrackAccess<u32>, TrackAccess<u32>>();
+
+        let mut guard = write.guard();
+        guard.insert(TrackAccess::new(1), TrackAccess::new(0));
+        guard.insert(TrackAccess::new(2), TrackAccess::new(0));
+        drop(guard);
+
+        // Every publish updates all of the entries at once, and drops the previous values once
+        // they are replayed on the other map
+        let t1 = thread::spawn(move || {
+            for generation in 1..=2 {
+                let mut guard = write.guard();
+                guard.replace(TrackAccess::new(1), |_| TrackAccess::new(generation));
+                guard.replace(TrackAccess::new(2), |_| TrackAccess::new(generation));
+            }
+        });
+
+        let t2 = thread::spawn(move || {
+            let guard = read.guard();
+            let first = guard
+                .iter()
+                .map(|(_, value)| *value.get())
+                .collect::<Vec<_>>();
+            assert_eq!(first.len(), 2);
+            assert_eq!(first[0], first[1]);
+
+            // The snapshot does not change while the guard is held
+            let second = guard
+                .iter()
+                .map(|(_, value)| *value.get())
+                .collect::<Vec<_>>();
+            assert_eq!(first, second);
+        });
+
+        t1.join().unwrap();
+        t2.join().unwrap();
+    });
+}
+
+#[test]
+pub fn snapshot_version_matches_contents() {
+    util::maybe_loom_model(|| {
+        let (mut write, read) = flashmap::new::<TrackAccess<u32>, TrackAccess<u64>>();
+
+        // Every publish stores the version it creates as the value
+        let t1 = thread::spawn(move || {
+            for version in 1..=2 {
+                write
+                    .guard()
+                    .insert(TrackAccess::new(1), TrackAccess::new(version));
+            }
+        });
+
+        let t2 = thread::spawn(move || {
+            let (value, version) = read.read(|guard| guard.get(&1).map(|value| *value.get()));
+            assert_eq!(value.unwrap_or(0), version);
+        });
+
+        t1.join().unwrap();
+        t2.join().unwrap();
+    });
+}
+
 // WARNING: this test takes about 20 minutes to run on my AMD 9 Ryzen 5900X with the loomtest-fast
 // profile. That being said, this test has caught bugs that the others have not.
 #[test]
@@ -75,3 +75,151 @@ pub fn reclamation() {
     drop(guard);
     drop(read);
 }
+
+#[test]
+pub fn owned_guard_outlives_handle() {
+    let (mut write, read) = flashmap::new::<Box<u32>, Box<u32>>();
+
+    write.guard().insert(Box::new(10), Box::new(20));
+
+    let guard = read.guard_owned();
+    drop(read);
+
+    let t1 = thread::spawn(move || {
+        write.guard().insert(Box::new(10), Box::new(30));
+        write.guard().remove(Box::new(10));
+        write
+    });
+
+    // The owned guard keeps its snapshot alive while the writer proceeds
+    assert_eq!(guard.get(&10).map(Deref::deref), Some(&20));
+    let guard = thread::spawn(move || {
+        assert_eq!(guard.len(), 1);
+        guard
+    })
+    .join()
+    .unwrap();
+    drop(guard);
+
+    let write = t1.join().unwrap();
+    assert!(!write.contains_key(&10));
+}
+
+#[test]
+pub fn try_reclaim_with_lagging_reader() {
+    let (mut write, read) = flashmap::new::<Box<u32>, Box<u32>>();
+
+    write.guard().insert(Box::new(10), Box::new(20));
+
+    let guard = read.guard();
+    let twenty = guard.get(&10).unwrap();
+    write.guard().remove(Box::new(10));
+
+    // The reader still observes the removed value, so it cannot be reclaimed
+    assert!(!write.try_reclaim());
+    assert_eq!(**twenty, 20);
+    drop(guard);
+
+    assert!(write.try_reclaim());
+    assert!(read.guard().get(&10).is_none());
+
+    // Nothing is pending, so this should trivially succeed
+    assert!(write.try_reclaim());
+    write.guard().insert(Box::new(10), Box::new(30));
+    assert_eq!(**read.guard().get(&10).unwrap(), 30);
+}
+
+#[test]
+pub fn evicted_key_outlives_operation_log_access() {
+    let (mut write, read) = flashmap::Builder::new()
+        .versioned()
+        .build::<Box<u32>, Box<u32>>();
+
+    let mut guard = write.guard();
+    for i in 0..4 {
+        guard.insert(Box::new(i), Box::new(i));
+    }
+    guard.publish();
+
+    let mut guard = write.guard();
+    let replaced = guard.insert(Box::new(1), Box::new(10)).unwrap();
+    let removed = guard.remove(Box::new(2)).unwrap();
+
+    // Looking up versions hashes the keys held by the operation log
+    assert!(guard
+        .replace_if_version(Box::new(3), 0, Box::new(30))
+        .is_err());
+    guard.insert(Box::new(4), Box::new(4));
+
+    assert_eq!(**Evicted::key(&replaced), 1);
+    assert_eq!(**Evicted::key(&removed), 2);
+    assert_eq!(**replaced, 1);
+    guard.publish();
+
+    write.guard().remove(Box::new(1));
+    assert_eq!(read.guard().len(), 3);
+}
+
+#[test]
+pub fn eager_reclaim_drops_values_once() {
+    let (mut write, read) = flashmap::Builder::new()
+        .eager_reclaim()
+        .build::<Box<u32>, Box<u32>>();
+
+    let mut guard = write.guard();
+    for i in 0..4 {
+        guard.insert(Box::new(i), Box::new(i));
+    }
+    guard.publish();
+
+    let reader = thread::spawn(move || {
+        let guard = read.guard();
+        let sum = guard.values().map(|value| **value).sum::<u32>();
+        drop(guard);
+        (read, sum)
+    });
+
+    let mut guard = write.guard();
+    guard.insert(Box::new(0), Box::new(10));
+    guard.insert(Box::new(0), Box::new(20));
+    let leaked = Evicted::leak(guard.remove(Box::new(1)).unwrap());
+    guard.remove(Box::new(2));
+    let lazily_dropped = Evicted::leak(guard.remove(Box::new(3)).unwrap());
+    guard.drop_lazily(lazily_dropped);
+    guard.publish();
+
+    let (read, sum) = reader.join().unwrap();
+    assert!(sum == 6 || sum == 20);
+    assert_eq!(*write.reclaim_one(leaked), 1);
+
+    write.guard().clear_and_shrink(0);
+    write.guard().insert(Box::new(5), Box::new(5));
+    assert_eq!(read.guard().len(), 1);
+}
+
+#[test]
+pub fn eager_reclaim_keeps_live_evicted_values() {
+    let (mut write, read) = flashmap::Builder::new()
+        .eager_reclaim()
+        .build::<Box<u32>, Box<u32>>();
+
+    let mut guard = write.guard();
+    for i in 0..3 {
+        guard.insert(Box::new(i), Box::new(i));
+    }
+    guard.publish();
+
+    // Evicted values may outlive the guard, so publishing must not hand them to readers
+    let mut guard = write.guard();
+    let dropped = guard.remove(Box::new(0)).unwrap();
+    let leaked = guard.remove(Box::new(1)).unwrap();
+    let ignored = guard.remove(Box::new(2)).unwrap();
+    drop(ignored);
+    drop(guard);
+    assert_eq!(**dropped, 0);
+    drop(dropped);
+    let leaked = Evicted::leak(leaked);
+
+    assert!(read.guard().is_empty());
+    assert_eq!(*write.reclaim_one(leaked), 1);
+}
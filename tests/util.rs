@@ -1,12 +1,19 @@
 #![allow(dead_code)]
 
+// Each test crate only uses some of these re-exports
 #[cfg(loom)]
+#[allow(unused_imports)]
 pub use loom::*;
 #[cfg(not(loom))]
+#[allow(unused_imports)]
 pub use std::{sync, thread};
 
+#[allow(unused_imports)]
 pub use track_access::*;
 
+use flashmap::{ReadHandle, WriteHandle};
+use std::hash::{BuildHasher, Hash};
+
 pub fn maybe_loom_model<F>(test: F)
 where
     F: Fn() + Send + Sync + 'static,
@@ -17,6 +24,23 @@ where
     test();
 }
 
+/// Brings the writer's copy of the map up to date, and checks that it holds the same entries as
+/// the copy seen by readers. Changes are replayed onto the writer's copy lazily, so this catches
+/// operations which were applied to one copy of the map differently than to the other.
+pub fn assert_both_copies_eq<K, V, S>(write: &mut WriteHandle<K, V, S>, read: &ReadHandle<K, V, S>)
+where
+    K: Hash + Eq,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    let writer = write.guard();
+    let reader = read.guard();
+    assert!(
+        writer == reader,
+        "The writer's copy of the map differs from the readers' copy"
+    );
+}
+
 #[cfg(loom)]
 mod track_access {
     use loom::{alloc::Track, cell::UnsafeCell};